@@ -0,0 +1,3 @@
+fn main() {
+    elrond_wasm_debug::meta::perform::<delegation_mock::AbiProvider>();
+}
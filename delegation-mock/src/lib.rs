@@ -0,0 +1,91 @@
+#![no_std]
+
+elrond_wasm::imports!();
+
+// stands in for a validator's delegation contract in the mandos scenarios.
+// undelegated EGLD can be withdrawn right away and rewards are set through the storage
+#[elrond_wasm::contract]
+pub trait DelegationMock {
+    #[init]
+    fn init(&self) {}
+
+    #[payable("EGLD")]
+    #[endpoint(delegate)]
+    fn delegate(&self) {
+        self.require_not_failing();
+
+        let caller = self.blockchain().get_caller();
+        let payment = self.call_value().egld_value();
+
+        self.active_stake(&caller).update(|stake| *stake += payment);
+    }
+
+    #[endpoint(unDelegate)]
+    fn undelegate(&self, amount: BigUint) {
+        self.require_not_failing();
+
+        let caller = self.blockchain().get_caller();
+        let stake = self.active_stake(&caller).get();
+        require!(stake >= amount, "Not enough active stake");
+
+        self.active_stake(&caller).set(&stake - &amount);
+        self.withdrawable(&caller).update(|withdrawable| *withdrawable += amount);
+    }
+
+    #[endpoint(withdraw)]
+    fn withdraw(&self) {
+        self.require_not_failing();
+
+        let caller = self.blockchain().get_caller();
+        let amount = self.withdrawable(&caller).get();
+        self.withdrawable(&caller).clear();
+
+        if amount > 0 {
+            self.send().direct_egld(&caller, &amount);
+        }
+    }
+
+    #[endpoint(reDelegateRewards)]
+    fn redelegate_rewards(&self) {
+        self.require_not_failing();
+
+        let caller = self.blockchain().get_caller();
+        let rewards = self.claimable_rewards(&caller).get();
+        self.claimable_rewards(&caller).clear();
+
+        self.active_stake(&caller).update(|stake| *stake += rewards);
+    }
+
+    #[endpoint(getUserActiveStake)]
+    fn get_user_active_stake(&self, address: ManagedAddress) -> BigUint {
+        self.require_not_failing();
+
+        self.active_stake(&address).get()
+    }
+
+    #[endpoint(getClaimableRewards)]
+    fn get_claimable_rewards(&self, address: ManagedAddress) -> BigUint {
+        self.require_not_failing();
+
+        self.claimable_rewards(&address).get()
+    }
+
+    fn require_not_failing(&self) {
+        require!(!self.failing().get(), "Delegation contract is failing");
+    }
+
+    // Storage
+
+    #[storage_mapper("active_stake")]
+    fn active_stake(&self, address: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("claimable_rewards")]
+    fn claimable_rewards(&self, address: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("withdrawable")]
+    fn withdrawable(&self, address: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    // every endpoint fails while set
+    #[storage_mapper("failing")]
+    fn failing(&self) -> SingleValueMapper<bool>;
+}
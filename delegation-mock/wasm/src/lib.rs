@@ -0,0 +1,19 @@
+////////////////////////////////////////////////////
+////////////////// AUTO-GENERATED //////////////////
+////////////////////////////////////////////////////
+
+#![no_std]
+
+elrond_wasm_node::wasm_endpoints! {
+    delegation_mock
+    (
+        delegate
+        getClaimableRewards
+        getUserActiveStake
+        reDelegateRewards
+        unDelegate
+        withdraw
+    )
+}
+
+elrond_wasm_node::wasm_empty_callback! {}
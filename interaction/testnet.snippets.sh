@@ -71,6 +71,17 @@ stake() {
         --send --proxy=${PROXY} --chain=${CHAIN_ID}
 }
 
+stakeWithReferral() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} \
+        --gas-limit=50000000 --value=1000000000000000000 --function="stakeWithReferral" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+claimReferralRewards() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="claimReferralRewards" \
+        --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
 unstake() {
     erdpy --verbose tx new --receiver=${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 \
         --send \
@@ -89,6 +100,33 @@ claim() {
     # --data="ESDTTransfer@${TOKEN_ID_HEX}@b1a2bc2ec50000@756e7374616b65" \
 }
 
+# claims uEGLD nonces 1 and 2, the ones still locked are sent back
+claimBatch() {
+    erdpy --verbose tx new --receiver=${MY_ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 \
+        --send \
+        --proxy=${PROXY} \
+        --data="MultiESDTNFTTransfer@${ADDRESS_HEX}@02@${UNDELEGATED_TOKEN_ID_HEX}@01@b1a2bc2ec50000@${UNDELEGATED_TOKEN_ID_HEX}@02@b1a2bc2ec50000@636c61696d" \
+        --chain=${CHAIN_ID}
+}
+
+# merges uEGLD nonces 1 and 2
+mergePositions() {
+    erdpy --verbose tx new --receiver=${MY_ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 \
+        --send \
+        --proxy=${PROXY} \
+        --data="MultiESDTNFTTransfer@${ADDRESS_HEX}@02@${UNDELEGATED_TOKEN_ID_HEX}@01@b1a2bc2ec50000@${UNDELEGATED_TOKEN_ID_HEX}@02@b1a2bc2ec50000@6d65726765506f736974696f6e73" \
+        --chain=${CHAIN_ID}
+}
+
+# splits uEGLD nonce 1 in two halves
+splitPosition() {
+    erdpy --verbose tx new --receiver=${MY_ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 \
+        --send \
+        --proxy=${PROXY} \
+        --data="ESDTNFTTransfer@${UNDELEGATED_TOKEN_ID_HEX}@01@b1a2bc2ec50000@${ADDRESS_HEX}@73706c6974506f736974696f6e@58d15e17628000@58d15e17628000" \
+        --chain=${CHAIN_ID}
+}
+
 setTotalStaked() {
     erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="set_total_staked" \
         --arguments=0x8ac7230489e80000 --send --proxy=${PROXY} --chain=${CHAIN_ID}
@@ -137,33 +175,22 @@ withdrawAdmin() {
         --send --proxy=${PROXY} --chain=${CHAIN_ID}
 }
 
-push_validators() {
-    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="push_validators" --arguments 0x${DELEGATION_ADDRESS_HEX} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+addValidator() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="addValidator" --arguments 0x${DELEGATION_ADDRESS_HEX} str:${1} ${2} ${3} --send --proxy=${PROXY} --chain=${CHAIN_ID}
 }
 
-setMappingIndex() {
-    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="setMappingIndex" --arguments=2 --send --proxy=${PROXY} --chain=${CHAIN_ID}
+# 0 = FetchRewards, 1 = Redelegate, 2 = Delegate, 3 = FetchStake, 4 = Withdraw, 5 = UpdateExchangeRate
+resetMaintenancePhase() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="resetMaintenancePhase" --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
 }
 
-setRewardsMappingIndex() {
-    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="setRewardsMappingIndex" --arguments=2 --send --proxy=${PROXY} --chain=${CHAIN_ID}
-}
 
 clearRewardsAmounts() {
     erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="clearRewardsAmounts" --send --proxy=${PROXY} --chain=${CHAIN_ID}
 }
 
-clearRewardsStarted() {
-    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="clearRewardsStarted" --send --proxy=${PROXY} --chain=${CHAIN_ID}
-}
 
-clearWithdrawStarted() {
-    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="clearWithdrawStarted" --send --proxy=${PROXY} --chain=${CHAIN_ID}
-}
 
-clearRewardsFinished() {
-    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="clearRewardsFinished" --send --proxy=${PROXY} --chain=${CHAIN_ID}
-}
 
 setServiceFee() {
     erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="setServiceFee" \
@@ -175,18 +202,204 @@ setValidatorStakeAmount() {
         --arguments 0x${DELEGATION_ADDRESS_HEX} 0x8ac7230489e80000 --send --proxy=${PROXY} --chain=${CHAIN_ID}
 }
 
+# address, name and share (bps) of every recipient, shares must sum up to 10000
+setRevenueRecipients() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="setRevenueRecipients" \
+        --arguments $@ --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+# address, stake and rewards of every validator, in the order of getValidators
+submitEpochReport() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=100000000 --function="submitEpochReport" \
+        --arguments $@ --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+executeTimelockedChange() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="executeTimelockedChange" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+cancelTimelockedChange() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="cancelTimelockedChange" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
 distributeProtocolRevenue() {
     erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="distributeProtocolRevenue" \
         --send --proxy=${PROXY} --chain=${CHAIN_ID}
 }
 
+# 0 = Admin, 1 = Operator, 2 = FeeManager, 3 = Pauser, 4 = ValidatorManager, 5 = Reporter
+grantRole() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="grantRole" \
+        --arguments ${1} ${2} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+revokeRole() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="revokeRole" \
+        --arguments ${1} ${2} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+pauseAll() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="pauseAll" \
+        --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+resumeAll() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="resumeAll" \
+        --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+# stake, unstake, instantUnstake or claim
+pauseFeature() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="pauseFeature" \
+        --arguments str:${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+resumeFeature() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="resumeFeature" \
+        --arguments str:${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+setTvlCap() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="setTvlCap" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+setDepositLimit() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="setDepositLimit" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+setMinStakeValue() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="setMinStakeValue" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+setMinUnstakeValue() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="setMinUnstakeValue" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+setAllowlistEnabled() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="setAllowlistEnabled" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+addToAllowlist() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="addToAllowlist" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+removeFromAllowlist() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="removeFromAllowlist" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+importAllowlist() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="importAllowlist" \
+        --arguments $@ --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+# locks 1 stEGLD on the proposal, the action is passed nested-encoded
+propose() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 \
+        --function="ESDTTransfer" --arguments str:${TOKEN_ID} 0x0de0b6b3a7640000 str:propose ${1} \
+        --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+# proposal id, then 0x01 for yes or 0x for no
+vote() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 \
+        --function="ESDTTransfer" --arguments str:${TOKEN_ID} 0x0de0b6b3a7640000 str:vote ${1} ${2} \
+        --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+executeProposal() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="executeProposal" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+withdrawVote() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="withdrawVote" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+setKeeperMode() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="setKeeperMode" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+setKeeperBounty() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="setKeeperBounty" \
+        --arguments ${1} --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+claimKeeperRewards() {
+    erdpy --verbose contract call ${ADDRESS} --recall-nonce --pem=${ALICE} --gas-limit=50000000 --function="claimKeeperRewards" \
+        --send --proxy=${PROXY} --chain=${CHAIN_ID}
+}
+
+getRoleMembers() {
+    erdpy --verbose contract query ${ADDRESS} --function="getRoleMembers" --arguments ${1} --proxy=${PROXY}
+}
+
+getPauseState() {
+    erdpy --verbose contract query ${ADDRESS} --function="getPauseState" --proxy=${PROXY}
+}
+
+isAllowlisted() {
+    erdpy --verbose contract query ${ADDRESS} --function="isAllowlisted" --arguments ${1} --proxy=${PROXY}
+}
+
+getReferralRewards() {
+    erdpy --verbose contract query ${ADDRESS} --function="getReferralRewards" --arguments ${1} --proxy=${PROXY}
+}
+
+getRevenueRecipients() {
+    erdpy --verbose contract query ${ADDRESS} --function="getRevenueRecipients" --proxy=${PROXY}
+}
+
+getProtocolRevenueHistory() {
+    erdpy --verbose contract query ${ADDRESS} --function="getProtocolRevenueHistory" --proxy=${PROXY}
+}
+
+getUnmintedFees() {
+    erdpy --verbose contract query ${ADDRESS} --function="getUnmintedFees" --proxy=${PROXY}
+}
+
+getRateAtEpoch() {
+    erdpy --verbose contract query ${ADDRESS} --function="getRateAtEpoch" --arguments ${1} --proxy=${PROXY}
+}
+
+getRateHistory() {
+    erdpy --verbose contract query ${ADDRESS} --function="getRateHistory" --arguments ${1} ${2} ${3} --proxy=${PROXY}
+}
+
+getTwapRate() {
+    erdpy --verbose contract query ${ADDRESS} --function="getTwapRate" --arguments ${1} --proxy=${PROXY}
+}
+
+getYields() {
+    erdpy --verbose contract query ${ADDRESS} --function="getYields" --proxy=${PROXY}
+}
+
+getValidatorYield() {
+    erdpy --verbose contract query ${ADDRESS} --function="getValidatorYield" --arguments ${1} ${2} --proxy=${PROXY}
+}
+
+getProposal() {
+    erdpy --verbose contract query ${ADDRESS} --function="getProposal" --arguments ${1} --proxy=${PROXY}
+}
+
+getPendingTimelockedChanges() {
+    erdpy --verbose contract query ${ADDRESS} --function="getPendingTimelockedChanges" --proxy=${PROXY}
+}
+
 getValidators() {
     erdpy --verbose contract query ${ADDRESS} --function="getValidators" --proxy=${PROXY}
 }
 
-getWithdrawMappingIndex() {
-    erdpy --verbose contract query ${ADDRESS} --function="getWithdrawMappingIndex" --proxy=${PROXY}
-}
 
 getStakeValue() {
     erdpy --verbose contract query ${ADDRESS} --function="getStakeValue" --proxy=${PROXY}
@@ -196,13 +409,7 @@ getProtocolRevenue() {
     erdpy --verbose contract query ${ADDRESS} --function="getProtocolRevenue" --proxy=${PROXY}
 }
 
-getRedelegateStarted() {
-    erdpy --verbose contract query ${ADDRESS} --function="getRedelegateStarted" --proxy=${PROXY}
-}
 
-getRedelegateFinished() {
-    erdpy --verbose contract query ${ADDRESS} --function="getRedelegateFinished" --proxy=${PROXY}
-}
 
 getValidatorStakeAmount() {
     erdpy --verbose contract query ${ADDRESS} --function="getValidatorStakeAmount" --proxy=${PROXY}
@@ -212,17 +419,8 @@ getValidatorStakeAmountClone() {
     erdpy --verbose contract query ${ADDRESS} --function="getValidatorStakeAmountClone" --proxy=${PROXY}
 }
 
-getRewardsInfoFinished() {
-    erdpy --verbose contract query ${ADDRESS} --function="getRewardsInfoFinished" --proxy=${PROXY}
-}
 
-getStakeInfoFinished() {
-    erdpy --verbose contract query ${ADDRESS} --function="getStakeInfoFinished" --proxy=${PROXY}
-}
 
-getWithdrawFinished() {
-    erdpy --verbose contract query ${ADDRESS} --function="getWithdrawFinished" --proxy=${PROXY}
-}
 
 getRewardsAmounts() {
     erdpy --verbose contract query ${ADDRESS} --function="getRewardsAmounts" --proxy=${PROXY}
@@ -252,17 +450,11 @@ getCallbackResult() {
     erdpy --verbose contract query ${ADDRESS} --function="getCallbackResult" --proxy=${PROXY}
 }
 
-getMappingIndex() {
-    erdpy --verbose contract query ${ADDRESS} --function="getMappingIndex" --proxy=${PROXY}
+getMaintenanceState() {
+    erdpy --verbose contract query ${ADDRESS} --function="getMaintenanceState" --proxy=${PROXY}
 }
 
-getRedelegateMappingIndex() {
-    erdpy --verbose contract query ${ADDRESS} --function="getRedelegateMappingIndex" --proxy=${PROXY}
-}
 
-getRewardsMappingIndex() {
-    erdpy --verbose contract query ${ADDRESS} --function="getRewardsMappingIndex" --proxy=${PROXY}
-}
 
 getTotalStaked() {
     erdpy --verbose contract query ${ADDRESS} --function="getTotalStaked" --proxy=${PROXY}
@@ -289,8 +481,8 @@ getExchangeRateMultiplier() {
     erdpy --verbose contract query ${ADDRESS} --function="getExchangeRateMultiplier" --proxy=${PROXY}
 }
 
-getDailyDelegationFinished() {
-    erdpy --verbose contract query ${ADDRESS} --function="getDailyDelegationFinished" --proxy=${PROXY}
+getMaintenancePhase() {
+    erdpy --verbose contract query ${ADDRESS} --function="getMaintenancePhase" --proxy=${PROXY}
 }
 
 getUEgldId() {
@@ -0,0 +1,249 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::helpers::MAX_BPS;
+use crate::storage::DelegationPlanItem;
+use crate::validators::ValidatorStatus;
+
+// the delegation contract rejects delegations below 1 EGLD
+pub const MIN_DELEGATION_AMOUNT: u64 = 1_000_000_000_000_000_000;
+
+#[elrond_wasm::module]
+pub trait AllocationModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    + crate::scoring::ScoringModule
+    {
+
+    // splits the amount between the validators so their stake gets closer to their target weight.
+    // validators below target are filled first (up to their cap), the rest is spread by weight.
+    // allocations under 1 EGLD are left in delta_stake for the next epoch
+    fn compute_delegation_plan(&self, amount: &BigUint) -> ManagedVec<DelegationPlanItem<Self::Api>> {
+        let mut plan = ManagedVec::new();
+        let total_weight = self.get_total_validator_weight(false);
+
+        if total_weight == 0 {
+            return plan;
+        }
+
+        let total_after = self.get_total_validator_stake() + amount;
+
+        let mut total_wanted = BigUint::zero();
+        for validator in self.validators().iter() {
+            total_wanted += self.get_validator_deficit(&validator, &total_after, total_weight);
+        }
+
+        let remaining = if &total_wanted < amount {
+            amount - &total_wanted
+        } else {
+            BigUint::zero()
+        };
+
+        for validator in self.validators().iter() {
+            let deficit = self.get_validator_deficit(&validator, &total_after, total_weight);
+
+            let mut allocation = if &total_wanted > amount {
+                amount * &deficit / &total_wanted
+            } else {
+                deficit
+            };
+
+            if remaining > 0 {
+                let weight = self.get_validator_weight(&validator, false);
+                let extra = &remaining * &BigUint::from(weight) / total_weight;
+
+                allocation += match self.get_validator_room(&validator) {
+                    Some(room) if room > allocation => min_big_uint(extra, room - &allocation),
+                    Some(_) => BigUint::zero(),
+                    None => extra,
+                };
+            }
+
+            if allocation >= BigUint::from(MIN_DELEGATION_AMOUNT) {
+                plan.push(DelegationPlanItem {
+                    validator,
+                    amount: allocation,
+                });
+            }
+        }
+
+        plan
+    }
+
+    // takes the amount from the validators above their target weight first,
+    // the rest proportionally to the stake left on each validator
+    fn compute_undelegation_plan(&self, amount: &BigUint) -> ManagedVec<DelegationPlanItem<Self::Api>> {
+        let mut plan = ManagedVec::new();
+        let total_weight = self.get_total_validator_weight(true);
+        let total_stake = self.get_total_validator_stake();
+
+        let total_after = if &total_stake > amount {
+            &total_stake - amount
+        } else {
+            BigUint::zero()
+        };
+
+        let mut total_excess = BigUint::zero();
+        for validator in self.validators().iter() {
+            total_excess += self.get_validator_excess(&validator, &total_after, total_weight);
+        }
+
+        let remaining = if &total_excess < amount {
+            amount - &total_excess
+        } else {
+            BigUint::zero()
+        };
+        let stake_below_target = &total_stake - &total_excess;
+
+        for validator in self.validators().iter() {
+            let stake = self.get_validator_stake(&validator);
+            let excess = self.get_validator_excess(&validator, &total_after, total_weight);
+
+            let mut allocation = if &total_excess > amount {
+                amount * &excess / &total_excess
+            } else {
+                excess.clone()
+            };
+
+            if remaining > 0 && stake_below_target > 0 {
+                allocation += &remaining * &(&stake - &excess) / &stake_below_target;
+            }
+
+            allocation = min_big_uint(allocation, stake.clone());
+
+            // a position under 1 EGLD can't stay delegated, so take all of it
+            if &stake - &allocation < BigUint::from(MIN_DELEGATION_AMOUNT) {
+                allocation = stake;
+            }
+
+            if allocation >= BigUint::from(MIN_DELEGATION_AMOUNT) {
+                plan.push(DelegationPlanItem {
+                    validator,
+                    amount: allocation,
+                });
+            }
+        }
+
+        plan
+    }
+
+    // Helpers
+
+    #[inline]
+    fn get_validator_stake(&self, validator: &ManagedAddress) -> BigUint {
+        match self.validator_stake_amount().get(validator) {
+            Some(n) => n,
+            None => BigUint::zero(),
+        }
+    }
+
+    fn get_total_validator_stake(&self) -> BigUint {
+        let mut total_stake = BigUint::zero();
+        for validator in self.validators().iter() {
+            total_stake += self.get_validator_stake(&validator);
+        }
+
+        total_stake
+    }
+
+    // target weight scaled by the validator score, so underperforming validators
+    // get less new stake and are undelegated from first.
+    // paused validators keep their share when undelegating but get no new stake,
+    // exiting validators have no target at all, so they are drained first
+    fn get_validator_weight(&self, validator: &ManagedAddress, include_paused: bool) -> u64 {
+        if self.validator_info(validator).is_empty() {
+            return 0;
+        }
+
+        let info = self.validator_info(validator).get();
+        let target_weight = match info.status {
+            ValidatorStatus::Active => info.target_weight,
+            ValidatorStatus::Paused if include_paused => info.target_weight,
+            _ => 0,
+        };
+
+        target_weight * self.get_score_factor(validator) / MAX_BPS
+    }
+
+    fn get_total_validator_weight(&self, include_paused: bool) -> u64 {
+        let mut total_weight = 0u64;
+        for validator in self.validators().iter() {
+            total_weight += self.get_validator_weight(&validator, include_paused);
+        }
+
+        total_weight
+    }
+
+    // how much more the validator can take before hitting its cap.
+    // None means the validator has no cap
+    fn get_validator_room(&self, validator: &ManagedAddress) -> Option<BigUint> {
+        let weight = self.get_validator_weight(validator, false);
+        if weight == 0 {
+            return Some(BigUint::zero());
+        }
+
+        let cap = self.validator_info(validator).get().cap;
+        let stake = self.get_validator_stake(validator);
+
+        if cap == 0 {
+            return None;
+        }
+
+        if cap > stake {
+            Some(cap - stake)
+        } else {
+            Some(BigUint::zero())
+        }
+    }
+
+    fn get_validator_target(
+        &self,
+        validator: &ManagedAddress,
+        total: &BigUint,
+        total_weight: u64,
+        include_paused: bool,
+    ) -> BigUint {
+        if total_weight == 0 {
+            return BigUint::zero();
+        }
+
+        let weight = self.get_validator_weight(validator, include_paused);
+
+        total * &BigUint::from(weight) / total_weight
+    }
+
+    fn get_validator_deficit(&self, validator: &ManagedAddress, total: &BigUint, total_weight: u64) -> BigUint {
+        let stake = self.get_validator_stake(validator);
+        let target = self.get_validator_target(validator, total, total_weight, false);
+        let deficit = if target > stake {
+            target - stake
+        } else {
+            BigUint::zero()
+        };
+
+        match self.get_validator_room(validator) {
+            Some(room) => min_big_uint(deficit, room),
+            None => deficit,
+        }
+    }
+
+    fn get_validator_excess(&self, validator: &ManagedAddress, total: &BigUint, total_weight: u64) -> BigUint {
+        let stake = self.get_validator_stake(validator);
+        let target = self.get_validator_target(validator, total, total_weight, true);
+
+        if stake > target {
+            stake - target
+        } else {
+            BigUint::zero()
+        }
+    }
+}
+
+fn min_big_uint<M: ManagedTypeApi>(a: BigUint<M>, b: BigUint<M>) -> BigUint<M> {
+    if a < b {
+        a
+    } else {
+        b
+    }
+}
@@ -0,0 +1,75 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::roles::Role;
+
+#[elrond_wasm::module]
+pub trait AllowlistModule:
+    crate::roles::RolesModule
+    + crate::events::EventsModule
+    {
+
+    // Storage
+    // - when enabled, only allowlisted addresses can stake and unstake.
+    //   stEGLD transfers and uEGLD claims are not restricted
+
+    #[view(isAllowlistEnabled)]
+    #[storage_mapper("allowlist_enabled")]
+    fn allowlist_enabled(&self) -> SingleValueMapper<bool>;
+
+    #[view(getAllowlist)]
+    #[storage_mapper("allowlist")]
+    fn allowlist(&self) -> UnorderedSetMapper<ManagedAddress>;
+
+    #[endpoint(setAllowlistEnabled)]
+    fn set_allowlist_enabled(&self, enabled: bool) {
+        self.require_role(Role::Admin);
+
+        self.allowlist_enabled().set(enabled);
+        self.allowlist_mode_changed_event(enabled);
+    }
+
+    #[endpoint(addToAllowlist)]
+    fn add_to_allowlist(&self, address: ManagedAddress) {
+        self.require_role(Role::Admin);
+
+        require!(self.allowlist().insert(address.clone()), "Address already allowlisted");
+
+        self.allowlist_changed_event(&address, true);
+    }
+
+    #[endpoint(removeFromAllowlist)]
+    fn remove_from_allowlist(&self, address: ManagedAddress) {
+        self.require_role(Role::Admin);
+
+        require!(self.allowlist().swap_remove(&address), "Address is not allowlisted");
+
+        self.allowlist_changed_event(&address, false);
+    }
+
+    // addresses already on the list are skipped
+    #[endpoint(importAllowlist)]
+    fn import_allowlist(&self, addresses: MultiValueEncoded<ManagedAddress>) {
+        self.require_role(Role::Admin);
+
+        for address in addresses {
+            if self.allowlist().insert(address.clone()) {
+                self.allowlist_changed_event(&address, true);
+            }
+        }
+    }
+
+    #[view(isAllowlisted)]
+    fn is_allowlisted(&self, address: ManagedAddress) -> bool {
+        self.allowlist().contains(&address)
+    }
+
+    // Helpers
+
+    fn require_allowlisted(&self, address: &ManagedAddress) {
+        require!(
+            !self.allowlist_enabled().get() || self.allowlist().contains(address),
+            "Address is not allowlisted"
+        );
+    }
+}
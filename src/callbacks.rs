@@ -4,7 +4,7 @@ elrond_wasm::derive_imports!();
 use crate::storage::StakeAmount;
 
 #[elrond_wasm::module]
-pub trait CallbacksModule: crate::storage::StorageModule + crate::events::EventsModule {
+pub trait CallbacksModule: crate::storage::StorageModule + crate::roles::RolesModule + crate::events::EventsModule {
 
    
 
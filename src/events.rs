@@ -1,8 +1,345 @@
 elrond_wasm::derive_imports!();
 elrond_wasm::imports!();
 
+use crate::governance::ProposalStatus;
+use crate::maintenance::MaintenancePhase;
+use crate::roles::Role;
+use crate::timelock::TimelockAction;
+
+// the async calls made to the delegation contracts
+#[derive(TypeAbi, TopEncode, Clone, Copy)]
+pub enum DelegationOperation {
+    GetStake,
+    GetRewards,
+    Delegate,
+    Undelegate,
+    Redelegate,
+    Withdraw,
+    ExitUndelegate,
+    ExitWithdraw,
+}
+
+#[derive(TypeAbi, TopEncode)]
+pub struct AsyncCallResultEvent<M: ManagedTypeApi> {
+    pub amount: BigUint<M>,
+    pub success: bool,
+    pub error_code: u32,
+    pub error_message: ManagedBuffer<M>,
+}
+
+#[derive(TypeAbi, TopEncode)]
+pub struct ExchangeRateUpdatedEvent<M: ManagedTypeApi> {
+    pub old_rate: BigUint<M>,
+    pub new_rate: BigUint<M>,
+    pub total_pooled_egld: BigUint<M>,
+    pub total_token_supply: BigUint<M>,
+}
+
+#[derive(TypeAbi, TopEncode)]
+pub struct EpochReportEvent<M: ManagedTypeApi> {
+    pub total_stake: BigUint<M>,
+    pub total_rewards: BigUint<M>,
+    pub exchange_rate: BigUint<M>,
+}
+
+#[derive(TypeAbi, TopEncode)]
+pub struct RevenuePayoutEvent<M: ManagedTypeApi> {
+    pub name: ManagedBuffer<M>,
+    pub share: u64,
+    pub egld_amount: BigUint<M>,
+    pub st_egld_amount: BigUint<M>,
+}
+
+// shared payload of the user flow events, fields that don't apply are 0
+#[derive(TypeAbi, TopEncode)]
+pub struct UserOperationEvent<M: ManagedTypeApi> {
+    pub egld_amount: BigUint<M>,
+    pub st_egld_amount: BigUint<M>,
+    pub uegld_nonce: u64,
+    pub unlock_epoch: u64,
+    pub fee: BigUint<M>,
+    pub exchange_rate: BigUint<M>,
+    pub timestamp: u64,
+}
+
 #[elrond_wasm::module]
 pub trait EventsModule {
+    // User flows
+
+    #[event("stake")]
+    fn stake_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] epoch: u64,
+        data: &UserOperationEvent<Self::Api>,
+    );
+
+    #[event("unstake")]
+    fn unstake_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] epoch: u64,
+        data: &UserOperationEvent<Self::Api>,
+    );
+
+    #[event("instant-unstake")]
+    fn instant_unstake_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] epoch: u64,
+        data: &UserOperationEvent<Self::Api>,
+    );
+
+    #[event("claim")]
+    fn claim_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] epoch: u64,
+        data: &UserOperationEvent<Self::Api>,
+    );
+
+    // uEGLD positions
+
+    #[event("positions-merged")]
+    fn positions_merged_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] nonce: u64,
+        #[indexed] unlock_epoch: u64,
+        egld_amount: &BigUint,
+    );
+
+    #[event("position-split")]
+    fn position_split_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] nonce: u64,
+        #[indexed] parts: usize,
+        egld_amount: &BigUint,
+    );
+
+    // Maintenance
+
+    #[event("maintenance-phase-started")]
+    fn maintenance_phase_started_event(
+        &self,
+        #[indexed] epoch: u64,
+        #[indexed] phase: MaintenancePhase,
+    );
+
+    #[event("maintenance-phase-finished")]
+    fn maintenance_phase_finished_event(
+        &self,
+        #[indexed] epoch: u64,
+        #[indexed] phase: MaintenancePhase,
+    );
+
+    #[event("async-call-result")]
+    fn async_call_result_event(
+        &self,
+        #[indexed] epoch: u64,
+        #[indexed] validator: &ManagedAddress,
+        #[indexed] operation: DelegationOperation,
+        data: &AsyncCallResultEvent<Self::Api>,
+    );
+
+    #[event("exchange-rate-updated")]
+    fn exchange_rate_updated_event(
+        &self,
+        #[indexed] epoch: u64,
+        data: &ExchangeRateUpdatedEvent<Self::Api>,
+    );
+
+    #[event("epoch-report-submitted")]
+    fn epoch_report_submitted_event(
+        &self,
+        #[indexed] epoch: u64,
+        #[indexed] reporter: &ManagedAddress,
+        data: &EpochReportEvent<Self::Api>,
+    );
+
+    fn emit_async_call_success(
+        &self,
+        epoch: u64,
+        validator: &ManagedAddress,
+        operation: DelegationOperation,
+        amount: &BigUint,
+    ) {
+        self.async_call_result_event(
+            epoch,
+            validator,
+            operation,
+            &AsyncCallResultEvent {
+                amount: amount.clone(),
+                success: true,
+                error_code: 0,
+                error_message: ManagedBuffer::new(),
+            },
+        );
+    }
+
+    fn emit_async_call_failure(
+        &self,
+        epoch: u64,
+        validator: &ManagedAddress,
+        operation: DelegationOperation,
+        amount: &BigUint,
+        error_code: u32,
+        error_message: &ManagedBuffer,
+    ) {
+        self.async_call_result_event(
+            epoch,
+            validator,
+            operation,
+            &AsyncCallResultEvent {
+                amount: amount.clone(),
+                success: false,
+                error_code,
+                error_message: error_message.clone(),
+            },
+        );
+    }
+
+    // Revenue
+
+    #[event("revenue-payout")]
+    fn revenue_payout_event(
+        &self,
+        #[indexed] recipient: &ManagedAddress,
+        #[indexed] epoch: u64,
+        data: &RevenuePayoutEvent<Self::Api>,
+    );
+
+    // Keepers
+
+    #[event("keeper-reward")]
+    fn keeper_reward_event(
+        &self,
+        #[indexed] keeper: &ManagedAddress,
+        #[indexed] epoch: u64,
+        #[indexed] phase: MaintenancePhase,
+        bounty: &BigUint,
+    );
+
+    #[event("keeper-rewards-claimed")]
+    fn keeper_rewards_claimed_event(
+        &self,
+        #[indexed] keeper: &ManagedAddress,
+        #[indexed] egld_amount: &BigUint,
+        st_egld_amount: &BigUint,
+    );
+
+    // Roles
+
+    #[event("role-granted")]
+    fn role_granted_event(
+        &self,
+        #[indexed] role: Role,
+        #[indexed] address: &ManagedAddress,
+        #[indexed] granted_by: &ManagedAddress,
+    );
+
+    #[event("role-revoked")]
+    fn role_revoked_event(
+        &self,
+        #[indexed] role: Role,
+        #[indexed] address: &ManagedAddress,
+        #[indexed] revoked_by: &ManagedAddress,
+    );
+
+    // Pause
+
+    #[event("pause-state-changed")]
+    fn pause_state_changed_event(&self, #[indexed] paused: bool);
+
+    #[event("feature-toggled")]
+    fn feature_toggled_event(
+        &self,
+        #[indexed] feature: &ManagedBuffer,
+        #[indexed] enabled: bool,
+    );
+
+    // Referrals
+
+    #[event("referral-recorded")]
+    fn referral_recorded_event(
+        &self,
+        #[indexed] user: &ManagedAddress,
+        #[indexed] referrer: &ManagedAddress,
+    );
+
+    #[event("referral-expired")]
+    fn referral_expired_event(
+        &self,
+        #[indexed] user: &ManagedAddress,
+        #[indexed] referrer: &ManagedAddress,
+        removed_stake: &BigUint,
+    );
+
+    #[event("referral-rewards-claimed")]
+    fn referral_rewards_claimed_event(
+        &self,
+        #[indexed] referrer: &ManagedAddress,
+        #[indexed] egld_amount: &BigUint,
+        st_egld_amount: &BigUint,
+    );
+
+    // Governance
+
+    #[event("proposal-created")]
+    fn proposal_created_event(
+        &self,
+        #[indexed] proposal_id: usize,
+        #[indexed] proposer: &ManagedAddress,
+        #[indexed] epoch: u64,
+    );
+
+    #[event("vote-cast")]
+    fn vote_cast_event(
+        &self,
+        #[indexed] proposal_id: usize,
+        #[indexed] voter: &ManagedAddress,
+        #[indexed] support: bool,
+        amount: &BigUint,
+    );
+
+    #[event("proposal-finalized")]
+    fn proposal_finalized_event(
+        &self,
+        #[indexed] proposal_id: usize,
+        #[indexed] status: ProposalStatus,
+    );
+
+    // Timelock
+
+    #[event("timelock-queued")]
+    fn timelock_queued_event(
+        &self,
+        #[indexed] id: usize,
+        #[indexed] effective_epoch: u64,
+        action: &TimelockAction<Self::Api>,
+    );
+
+    #[event("timelock-executed")]
+    fn timelock_executed_event(&self, #[indexed] id: usize, #[indexed] epoch: u64);
+
+    #[event("timelock-cancelled")]
+    fn timelock_cancelled_event(&self, #[indexed] id: usize, #[indexed] epoch: u64);
+
+    // Allowlist
+
+    #[event("allowlist-mode-changed")]
+    fn allowlist_mode_changed_event(&self, #[indexed] enabled: bool);
+
+    #[event("allowlist-changed")]
+    fn allowlist_changed_event(
+        &self,
+        #[indexed] address: &ManagedAddress,
+        #[indexed] allowed: bool,
+    );
+
+    // Tokens
+
     #[event("issue-started")]
     fn issue_started_event(
         &self,
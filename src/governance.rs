@@ -0,0 +1,333 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::helpers::MAX_BPS;
+use crate::roles::Role;
+use crate::validators::{ValidatorStatus, MAX_VALIDATOR_WEIGHT};
+
+pub const DEFAULT_VOTING_PERIOD: u64 = 7;
+
+// set by init, 20% of the supply has to vote
+pub const DEFAULT_QUORUM: u64 = 2_000;
+
+// set by init, in whole stEGLD
+pub const DEFAULT_PROPOSAL_THRESHOLD: u64 = 100;
+
+// share of the votes a proposal adding a validator needs, new validators receive delegations
+pub const ADD_VALIDATOR_MAJORITY: u64 = 6_667;
+
+// the parameter changes stEGLD holders can vote on
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub enum ProposalAction<M: ManagedTypeApi> {
+    SetServiceFee(BigUint<M>),
+    // address, name, target weight and cap
+    AddValidator(ManagedAddress<M>, ManagedBuffer<M>, u64, BigUint<M>),
+    RetireValidator(ManagedAddress<M>),
+    SetValidatorWeight(ManagedAddress<M>, u64),
+}
+
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, Copy, PartialEq)]
+pub enum ProposalStatus {
+    Active,
+    Executed,
+    Defeated,
+    // passed, but the action could no longer be applied
+    Failed,
+}
+
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct Proposal<M: ManagedTypeApi> {
+    pub id: usize,
+    pub proposer: ManagedAddress<M>,
+    pub action: ProposalAction<M>,
+    pub status: ProposalStatus,
+    pub start_epoch: u64,
+    // last epoch votes are accepted
+    pub end_epoch: u64,
+    pub yes_votes: BigUint<M>,
+    pub no_votes: BigUint<M>,
+}
+
+// stEGLD locked by an address on a proposal
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct Vote<M: ManagedTypeApi> {
+    pub support: bool,
+    pub amount: BigUint<M>,
+}
+
+#[elrond_wasm::module]
+pub trait GovernanceModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    + crate::validators::ValidatorsModule
+    {
+
+    // Storage
+
+    #[storage_mapper("proposals")]
+    fn proposals(&self) -> VecMapper<Proposal<Self::Api>>;
+
+    #[view(getVote)]
+    #[storage_mapper("votes")]
+    fn votes(&self, proposal_id: usize, voter: &ManagedAddress) -> SingleValueMapper<Vote<Self::Api>>;
+
+    #[view(getVotingPeriod)]
+    #[storage_mapper("voting_period")]
+    fn voting_period(&self) -> SingleValueMapper<u64>;
+
+    // basis points of the stEGLD supply that has to vote for a proposal to pass
+    #[view(getQuorum)]
+    #[storage_mapper("quorum")]
+    fn quorum(&self) -> SingleValueMapper<u64>;
+
+    // stEGLD the proposer has to lock, counted as a yes vote
+    #[view(getProposalThreshold)]
+    #[storage_mapper("proposal_threshold")]
+    fn proposal_threshold(&self) -> SingleValueMapper<BigUint>;
+
+    #[endpoint(setVotingPeriod)]
+    fn set_voting_period(&self, epochs: u64) {
+        self.require_role(Role::Admin);
+
+        require!(epochs > 0, "Voting period must be at least 1 epoch");
+        self.voting_period().set(epochs);
+    }
+
+    #[endpoint(setQuorum)]
+    fn set_quorum(&self, quorum: u64) {
+        self.require_role(Role::Admin);
+
+        require!(quorum > 0, "Quorum must be above 0");
+        require!(quorum <= MAX_BPS, "Quorum cannot exceed 100%");
+        self.quorum().set(quorum);
+    }
+
+    #[endpoint(setProposalThreshold)]
+    fn set_proposal_threshold(&self, threshold: BigUint) {
+        self.require_role(Role::Admin);
+
+        require!(threshold > 0, "Proposal threshold must be above 0");
+        self.proposal_threshold().set(threshold);
+    }
+
+    // Proposals
+    // - votes lock stEGLD in the contract until the voting window is over
+    // - once the window is over anyone can execute the proposal. the tally is final
+    //   by then, so votes can be withdrawn whether it was executed or not
+
+    #[payable("*")]
+    #[endpoint(propose)]
+    fn propose(&self, action: ProposalAction<Self::Api>) -> usize {
+        let payment = self.require_st_egld_payment();
+        let caller = self.blockchain().get_caller();
+        let current_epoch = self.blockchain().get_block_epoch();
+
+        require!(self.quorum().get() > 0, "Quorum must be set before creating proposals");
+        require!(
+            payment >= self.proposal_threshold().get(),
+            "Not enough stEGLD locked to create a proposal"
+        );
+        require!(self.can_apply_proposal_action(&action), "Proposal action cannot be applied");
+
+        let id = self.proposals().len() + 1;
+        self.proposals().push(&Proposal {
+            id,
+            proposer: caller.clone(),
+            action,
+            status: ProposalStatus::Active,
+            start_epoch: current_epoch,
+            end_epoch: current_epoch + self.get_voting_period() - 1,
+            yes_votes: payment.clone(),
+            no_votes: BigUint::zero(),
+        });
+        self.votes(id, &caller).set(&Vote {
+            support: true,
+            amount: payment.clone(),
+        });
+
+        self.proposal_created_event(id, &caller, current_epoch);
+        self.vote_cast_event(id, &caller, true, &payment);
+
+        id
+    }
+
+    // more stEGLD can be added to a vote, on the same side
+    #[payable("*")]
+    #[endpoint(vote)]
+    fn vote(&self, proposal_id: usize, support: bool) {
+        let payment = self.require_st_egld_payment();
+        let caller = self.blockchain().get_caller();
+        let mut proposal = self.require_proposal(proposal_id);
+
+        require!(
+            proposal.status == ProposalStatus::Active
+                && self.blockchain().get_block_epoch() <= proposal.end_epoch,
+            "Voting is closed for this proposal"
+        );
+
+        let vote_mapper = self.votes(proposal_id, &caller);
+        let mut vote = if vote_mapper.is_empty() {
+            Vote {
+                support,
+                amount: BigUint::zero(),
+            }
+        } else {
+            vote_mapper.get()
+        };
+
+        require!(vote.support == support, "Cannot vote on both sides");
+
+        vote.amount += &payment;
+        vote_mapper.set(&vote);
+
+        if support {
+            proposal.yes_votes += &payment;
+        } else {
+            proposal.no_votes += &payment;
+        }
+        self.proposals().set(proposal_id, &proposal);
+
+        self.vote_cast_event(proposal_id, &caller, support, &payment);
+    }
+
+    #[endpoint(executeProposal)]
+    fn execute_proposal(&self, proposal_id: usize) -> ProposalStatus {
+        let proposal = self.require_proposal(proposal_id);
+
+        require!(proposal.status == ProposalStatus::Active, "Proposal already finalized");
+        require!(
+            self.blockchain().get_block_epoch() > proposal.end_epoch,
+            "Voting is still open for this proposal"
+        );
+
+        self.finalize_proposal(proposal)
+    }
+
+    #[endpoint(withdrawVote)]
+    fn withdraw_vote(&self, proposal_id: usize) {
+        let caller = self.blockchain().get_caller();
+        let proposal = self.require_proposal(proposal_id);
+
+        require!(
+            self.blockchain().get_block_epoch() > proposal.end_epoch,
+            "Voting is still open for this proposal"
+        );
+        require!(!self.votes(proposal_id, &caller).is_empty(), "Nothing to withdraw");
+
+        let vote = self.votes(proposal_id, &caller).get();
+        self.votes(proposal_id, &caller).clear();
+
+        self.send().direct_esdt(&caller, &self.staked_egld_id().get(), 0, &vote.amount);
+    }
+
+    // Views
+
+    #[view(getProposal)]
+    fn get_proposal(&self, proposal_id: usize) -> Proposal<Self::Api> {
+        self.require_proposal(proposal_id)
+    }
+
+    #[view(getProposalsCount)]
+    fn get_proposals_count(&self) -> usize {
+        self.proposals().len()
+    }
+
+    // Helpers
+
+    // a proposal passes with more yes than no votes, once enough of the supply voted.
+    // adding a validator needs ADD_VALIDATOR_MAJORITY of the votes.
+    // the action is checked again, a proposal that can't be applied anymore is marked failed
+    fn finalize_proposal(&self, mut proposal: Proposal<Self::Api>) -> ProposalStatus {
+        let total_votes = &proposal.yes_votes + &proposal.no_votes;
+        let quorum_amount =
+            self.total_token_supply().get() * BigUint::from(self.quorum().get()) / MAX_BPS;
+        let has_majority = match proposal.action {
+            ProposalAction::AddValidator(..) => {
+                &proposal.yes_votes * MAX_BPS >= &total_votes * ADD_VALIDATOR_MAJORITY
+            },
+            _ => proposal.yes_votes > proposal.no_votes,
+        };
+        let passed = total_votes > 0 && total_votes >= quorum_amount && has_majority;
+
+        proposal.status = if !passed {
+            ProposalStatus::Defeated
+        } else if self.can_apply_proposal_action(&proposal.action) {
+            self.execute_proposal_action(&proposal.action);
+            ProposalStatus::Executed
+        } else {
+            ProposalStatus::Failed
+        };
+        self.proposals().set(proposal.id, &proposal);
+
+        self.proposal_finalized_event(proposal.id, proposal.status);
+
+        proposal.status
+    }
+
+    fn execute_proposal_action(&self, action: &ProposalAction<Self::Api>) {
+        match action {
+            ProposalAction::SetServiceFee(fee) => {
+                self.update_service_fee(fee.clone());
+            },
+            ProposalAction::AddValidator(address, name, target_weight, cap) => {
+                self.register_validator(address.clone(), name.clone(), *target_weight, cap.clone());
+            },
+            ProposalAction::RetireValidator(address) => {
+                self.start_validator_retirement(address);
+            },
+            ProposalAction::SetValidatorWeight(address, target_weight) => {
+                self.update_validator_weight(address, *target_weight);
+            },
+        }
+    }
+
+    // mirrors the checks of the setters, so executing the action can't fail
+    fn can_apply_proposal_action(&self, action: &ProposalAction<Self::Api>) -> bool {
+        match action {
+            ProposalAction::SetServiceFee(fee) => fee <= &1000u64,
+            ProposalAction::AddValidator(address, _, target_weight, _) => {
+                let info_mapper = self.validator_info(address);
+                *target_weight <= MAX_VALIDATOR_WEIGHT
+                    && (info_mapper.is_empty() || info_mapper.get().status == ValidatorStatus::Removed)
+            },
+            ProposalAction::RetireValidator(address) => {
+                let info_mapper = self.validator_info(address);
+                !info_mapper.is_empty() && {
+                    let status = info_mapper.get().status;
+                    status == ValidatorStatus::Active || status == ValidatorStatus::Paused
+                }
+            },
+            ProposalAction::SetValidatorWeight(address, target_weight) => {
+                *target_weight <= MAX_VALIDATOR_WEIGHT && !self.validator_info(address).is_empty()
+            },
+        }
+    }
+
+    fn require_proposal(&self, proposal_id: usize) -> Proposal<Self::Api> {
+        require!(
+            proposal_id > 0 && proposal_id <= self.proposals().len(),
+            "Proposal does not exist"
+        );
+
+        self.proposals().get(proposal_id)
+    }
+
+    fn require_st_egld_payment(&self) -> BigUint {
+        let (token, _, payment) = self.call_value().single_esdt().into_tuple();
+
+        require!(token == self.staked_egld_id().get(), "Invalid token sent");
+        require!(payment > 0, "Cannot receive 0 amount");
+
+        payment
+    }
+
+    #[inline]
+    fn get_voting_period(&self) -> u64 {
+        if self.voting_period().is_empty() {
+            DEFAULT_VOTING_PERIOD
+        } else {
+            self.voting_period().get()
+        }
+    }
+}
@@ -1,74 +1,182 @@
 elrond_wasm::imports!();
 elrond_wasm::derive_imports!();
 
+use crate::validators::ExitPhase;
+
+// percentages of the new settings are expressed in basis points
+pub const MAX_BPS: u64 = 10_000;
+
+// referral revenue per referred EGLD is scaled by this value
+pub const REFERRAL_PRECISION: u64 = 1_000_000_000_000_000_000;
+
 #[elrond_wasm::module]
 pub trait HelpersModule: 
-    crate::storage::StorageModule {
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule {
 
-    // TODO: transform all the increment functions into a single function
-    
-    // TODO: transform increment index to increment index staking
+    // stEGLD = EGLD * exchange_rate / multiplier
     #[inline]
-    fn increment_index(&self) {
-       
-        let mapping_index = self.mapping_index().get();
-        let current_epoch = self.blockchain().get_block_epoch();
+    fn egld_to_st_egld(&self, egld_amount: &BigUint) -> BigUint {
+        let exchange_rate = self.exchange_rate().get();
+        let exchange_rate_multiplier = self.exchange_rate_multiplier().get();
 
-        self.mapping_index().set(&mapping_index + &1);
-        
-        if &mapping_index >= &(self.validators().len()) {
-            self.mapping_index().set(1 as usize);
-        }
+        egld_amount * &exchange_rate / &exchange_rate_multiplier
     }
 
     #[inline]
-    fn increment_index_rewards(&self) {
-        let mapping_index = self.rewards_mapping_index().get();
-        let current_epoch = self.blockchain().get_block_epoch();
+    fn st_egld_to_egld(&self, st_egld_amount: &BigUint) -> BigUint {
+        let exchange_rate = self.exchange_rate().get();
+        let exchange_rate_multiplier = self.exchange_rate_multiplier().get();
 
-        self.rewards_mapping_index().set(&mapping_index + &1);
-        
-        if &mapping_index >= &(self.validators().len()) {
-            self.rewards_mapping_index().set(1 as usize);
-        }
+        st_egld_amount * &exchange_rate_multiplier / &exchange_rate
     }
 
+    // the part of the TVL that should stay undelegated for instant unstakes
     #[inline]
-    fn increment_index_withdraw(&self) {
-        let mapping_index = self.withdraw_mapping_index().get();
-        let current_epoch = self.blockchain().get_block_epoch();
+    fn get_buffer_target_amount(&self) -> BigUint {
+        let total_token_supply = self.total_token_supply().get();
+        let tvl = self.st_egld_to_egld(&total_token_supply);
+
+        tvl * BigUint::from(self.buffer_target().get()) / MAX_BPS
+    }
 
-        self.withdraw_mapping_index().set(&mapping_index + &1);
-        
-        if &mapping_index >= &(self.validators().len()) {
-            self.withdraw_mapping_index().set(1 as usize);
+    // what the daily delegation can take out of the buffer right now
+    fn get_buffer_surplus(&self) -> BigUint {
+        let buffer = self.liquidity_buffer().get();
+        let buffer_target = self.get_buffer_target_amount();
+
+        if buffer > buffer_target {
+            buffer - buffer_target
+        } else {
+            BigUint::zero()
         }
     }
 
-    #[inline]
-    fn increment_index_redelegate(&self) {
-        let mapping_index = self.redelegate_mapping_index().get();
+    // EGLD withdrawn from a validator first completes its matured exit, that part goes
+    // back to the buffer to be delegated to the active validators.
+    // the rest is reserved for uEGLD claims
+    fn handle_withdrawn_egld(&self, validator: &ManagedAddress, amount: &BigUint) {
         let current_epoch = self.blockchain().get_block_epoch();
+        let pending_undelegation = self.pending_undelegation().get();
+        let mut claims_amount = amount.clone();
+
+        self.pending_undelegation().set(if &pending_undelegation > amount {
+            &pending_undelegation - amount
+        } else {
+            BigUint::zero()
+        });
+
+        let exit_mapper = self.validator_exit(validator);
+        if !exit_mapper.is_empty() {
+            let mut exit = exit_mapper.get();
+
+            if exit.phase == ExitPhase::Unbonding && current_epoch >= exit.unlock_epoch {
+                let exit_left = &exit.amount - &exit.withdrawn_amount;
+                let exit_amount = if &exit_left < amount {
+                    exit_left
+                } else {
+                    amount.clone()
+                };
 
-        self.redelegate_mapping_index().set(&mapping_index + &1);
-        
-        if &mapping_index >= &(self.validators().len()) {
-            self.redelegate_mapping_index().set(1 as usize);
+                exit.withdrawn_amount += &exit_amount;
+                if exit.withdrawn_amount >= exit.amount {
+                    exit.phase = ExitPhase::Completed;
+                }
+                exit_mapper.set(&exit);
+
+                self.liquidity_buffer()
+                    .set(&self.liquidity_buffer().get() + &exit_amount);
+                self.delta_stake()
+                    .set(&self.delta_stake().get() + &BigInt::from(exit_amount.clone()));
+
+                claims_amount -= &exit_amount;
+            }
         }
+
+        let withdrawn_egld = self.withdrawn_egld().get();
+        self.withdrawn_egld().set(&withdrawn_egld + &claims_amount);
     }
 
-    #[inline]
+    // called once the rewards of every validator were fetched.
+    // the fee adds up to what was not distributed yet, keeper bounties are taken from it
     fn update_protocol_revenue(&self, epoch: &u64) {
-        if self.rewards_info_finished().contains(&epoch) == true {
-            let rewards_value = self.rewards_amounts().get(&epoch);
-            let protocol_fee = self.service_fee().get();
-
-            self.protocol_revenue().set(match rewards_value {
-                Some(n) => n,
-                None => BigUint::from(0u64)
-            } * protocol_fee  / 1000u64);
+        // a restarted rewards fetch doesn't accrue the epoch twice
+        if self.protocol_revenue_history().contains_key(epoch) {
+            return;
         }
 
+        let rewards_value = self.rewards_amounts().get(&epoch);
+        let protocol_fee = self.service_fee().get();
+
+        let epoch_revenue = match rewards_value {
+            Some(n) => n,
+            None => BigUint::from(0u64)
+        } * protocol_fee  / 1000u64;
+
+        if epoch_revenue == 0 {
+            return;
+        }
+
+        let referral_revenue = self.accrue_referral_revenue(&epoch_revenue);
+
+        self.protocol_revenue().update(|revenue| *revenue += &epoch_revenue - &referral_revenue);
+        self.protocol_revenue_history().insert(*epoch, epoch_revenue.clone());
+        self.total_protocol_revenue().update(|revenue| *revenue += &epoch_revenue);
+        self.unminted_fees().update(|fees| *fees += &epoch_revenue);
+        self.last_fee_accrual_epoch().set(*epoch);
+    }
+
+    // fees are minted at the rate computed after they accrued, which leaves them out of
+    // the EGLD backing the supply. minting them adds exactly their value to the supply
+    // and leaves the rate unchanged, so holders are only diluted by the service fee
+    fn mint_fees(&self, egld_amount: &BigUint) -> BigUint {
+        require!(
+            self.last_rate_update_epoch().get() >= self.last_fee_accrual_epoch().get(),
+            "Exchange rate must be updated before minting fees"
+        );
+
+        let st_egld_id = self.staked_egld_id().get();
+        let st_egld_amount = self.egld_to_st_egld(egld_amount);
+        let unminted_fees = self.unminted_fees().get();
+
+        self.unminted_fees().set(if &unminted_fees > egld_amount {
+            &unminted_fees - egld_amount
+        } else {
+            BigUint::zero()
+        });
+        self.total_token_supply()
+            .set(&self.total_token_supply().get() + &st_egld_amount);
+
+        self.send().esdt_local_mint(&st_egld_id, 0, &st_egld_amount);
+
+        st_egld_amount
+    }
+
+    // the referrers' cut of the epoch revenue, in proportion to the stake they brought.
+    // it is spread per referred EGLD, each referrer collects it when settled
+    fn accrue_referral_revenue(&self, epoch_revenue: &BigUint) -> BigUint {
+        let referral_share = self.referral_share().get();
+        let total_referred_stake = self.total_referred_stake().get();
+        let total_pooled_egld = self.total_pooled_egld().get();
+
+        if referral_share == 0 || total_referred_stake == 0 || total_pooled_egld == 0 {
+            return BigUint::zero();
+        }
+
+        let referred_stake = if total_referred_stake < total_pooled_egld {
+            total_referred_stake.clone()
+        } else {
+            total_pooled_egld.clone()
+        };
+        let referral_revenue = epoch_revenue * &BigUint::from(referral_share) / MAX_BPS
+            * &referred_stake / &total_pooled_egld;
+        let revenue_per_stake = &referral_revenue * &BigUint::from(REFERRAL_PRECISION) / &total_referred_stake;
+
+        self.referral_revenue_per_stake().update(|value| *value += &revenue_per_stake);
+
+        // the rounding leftover stays in the unminted fees
+        revenue_per_stake * &total_referred_stake / REFERRAL_PRECISION
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,111 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::maintenance::MaintenancePhase;
+use crate::roles::Role;
+
+// an async step sent with less gas could run out of gas in its callback,
+// leaving the maintenance phase waiting for it forever
+pub const MIN_MAINTENANCE_STEP_GAS: u64 = 20_000_000;
+
+#[elrond_wasm::module]
+pub trait KeeperModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    + crate::helpers::HelpersModule
+    {
+
+    // Storage
+
+    // when enabled, anyone can advance the maintenance cycle
+    #[view(isKeeperModeEnabled)]
+    #[storage_mapper("keeper_mode_enabled")]
+    fn keeper_mode_enabled(&self) -> SingleValueMapper<bool>;
+
+    // EGLD taken from the protocol revenue for each successful maintenance step
+    #[view(getKeeperBounty)]
+    #[storage_mapper("keeper_bounty")]
+    fn keeper_bounty(&self) -> SingleValueMapper<BigUint>;
+
+    // EGLD value of the bounties earned by a keeper, paid in stEGLD on claim
+    #[view(getKeeperRewards)]
+    #[storage_mapper("keeper_rewards")]
+    fn keeper_rewards(&self, keeper: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[endpoint(setKeeperMode)]
+    fn set_keeper_mode(&self, enabled: bool) {
+        self.require_role(Role::Admin);
+
+        self.keeper_mode_enabled().set(enabled);
+    }
+
+    #[endpoint(setKeeperBounty)]
+    fn set_keeper_bounty(&self, bounty: BigUint) {
+        self.require_role(Role::FeeManager);
+
+        self.keeper_bounty().set(bounty);
+    }
+
+    #[endpoint(claimKeeperRewards)]
+    fn claim_keeper_rewards(&self) {
+        let caller = self.blockchain().get_caller();
+        let rewards = self.keeper_rewards(&caller).get();
+
+        require!(rewards > 0, "No keeper rewards to claim");
+
+        let st_egld_id = self.staked_egld_id().get();
+        let st_egld_amount = self.mint_fees(&rewards);
+
+        self.keeper_rewards(&caller).clear();
+        self.send().direct_esdt(&caller, &st_egld_id, 0, &st_egld_amount);
+
+        self.keeper_rewards_claimed_event(&caller, &rewards, &st_egld_amount);
+    }
+
+    // Helpers
+
+    fn require_keeper_or_operator(&self) {
+        if !self.keeper_mode_enabled().get() {
+            self.require_role(Role::Operator);
+        }
+    }
+
+    fn require_maintenance_step_gas(&self) {
+        require!(
+            self.blockchain().get_gas_left() >= MIN_MAINTENANCE_STEP_GAS,
+            "Not enough gas left for the maintenance step"
+        );
+    }
+
+    // called once a step of the maintenance succeeded. the state machine only
+    // hands out every step once, so a bounty can't be earned twice for the same step.
+    // bounties can't exceed the protocol revenue left and operators earn none
+    fn reward_keeper(&self, epoch: u64, phase: MaintenancePhase, keeper: &ManagedAddress) {
+        if !self.keeper_mode_enabled().get() {
+            return;
+        }
+
+        // operators crank as part of their role
+        if self.has_role(Role::Operator, keeper.clone()) {
+            return;
+        }
+
+        let protocol_revenue = self.protocol_revenue().get();
+        let bounty = self.keeper_bounty().get();
+        let bounty = if bounty < protocol_revenue {
+            bounty
+        } else {
+            protocol_revenue.clone()
+        };
+
+        if bounty == 0 {
+            return;
+        }
+
+        self.protocol_revenue().set(&protocol_revenue - &bounty);
+        self.keeper_rewards(keeper).update(|rewards| *rewards += &bounty);
+
+        self.keeper_reward_event(keeper, epoch, phase, &bounty);
+    }
+}
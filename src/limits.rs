@@ -0,0 +1,94 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::roles::Role;
+
+#[elrond_wasm::module]
+pub trait LimitsModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    {
+
+    // Storage
+    // - all limits are in EGLD, 0 means no limit
+    // - the minimum stake is kept in min_value
+
+    // maximum total pooled EGLD accepted by stake
+    #[view(getTvlCap)]
+    #[storage_mapper("tvl_cap")]
+    fn tvl_cap(&self) -> SingleValueMapper<BigUint>;
+
+    // maximum EGLD an address can stake over time, unstaking doesn't free it up
+    #[view(getDepositLimit)]
+    #[storage_mapper("deposit_limit")]
+    fn deposit_limit(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getUserDeposits)]
+    #[storage_mapper("user_deposits")]
+    fn user_deposits(&self, address: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[view(getMinUnstakeValue)]
+    #[storage_mapper("min_unstake_value")]
+    fn min_unstake_value(&self) -> SingleValueMapper<BigUint>;
+
+    #[endpoint(setTvlCap)]
+    fn set_tvl_cap(&self, cap: BigUint) {
+        self.require_role(Role::Admin);
+
+        self.tvl_cap().set(cap);
+    }
+
+    #[endpoint(setDepositLimit)]
+    fn set_deposit_limit(&self, limit: BigUint) {
+        self.require_role(Role::Admin);
+
+        self.deposit_limit().set(limit);
+    }
+
+    #[endpoint(setMinStakeValue)]
+    fn set_min_stake_value(&self, amount: BigUint) {
+        self.require_role(Role::Admin);
+
+        self.min_value().set(amount);
+    }
+
+    #[endpoint(setMinUnstakeValue)]
+    fn set_min_unstake_value(&self, amount: BigUint) {
+        self.require_role(Role::Admin);
+
+        self.min_unstake_value().set(amount);
+    }
+
+    // Checks
+
+    // also records the deposit against the address limit
+    fn check_stake_limits(&self, caller: &ManagedAddress, amount: &BigUint) {
+        require!(
+            amount >= &self.min_value().get(),
+            "Stake amount is below the minimum stake value"
+        );
+
+        let tvl_cap = self.tvl_cap().get();
+        require!(
+            tvl_cap == 0 || &(self.total_pooled_egld().get() + amount) <= &tvl_cap,
+            "Stake would exceed the total pooled EGLD cap"
+        );
+
+        let deposit_limit = self.deposit_limit().get();
+        let user_deposits = self.user_deposits(caller).get() + amount;
+        require!(
+            deposit_limit == 0 || user_deposits <= deposit_limit,
+            "Stake would exceed the deposit limit of this address"
+        );
+
+        self.user_deposits(caller).set(&user_deposits);
+    }
+
+    fn check_unstake_limits(&self, egld_amount: &BigUint) {
+        require!(
+            egld_amount >= &self.min_unstake_value().get(),
+            "Unstake amount is below the minimum unstake value"
+        );
+    }
+}
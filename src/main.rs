@@ -3,28 +3,72 @@
 elrond_wasm::imports!();
 elrond_wasm::derive_imports!();
 
+mod allocation;
+mod allowlist;
 mod callbacks;
 mod delegate;
 mod events;
+mod governance;
 mod helpers;
+mod keeper;
+mod limits;
 mod maintenance;
+mod oracle;
+mod positions;
+mod rate_history;
+mod referral;
+mod revenue;
+mod roles;
+mod scoring;
 mod storage;
+mod timelock;
+mod toggles;
 mod tokens;
+mod validators;
+mod yields;
 
 use crate::callbacks::CallbacksModule;
 use crate::heap::Vec;
 use crate::storage::StakeAmount;
-use crate::tokens::TokenAttributes;
+use crate::events::{DelegationOperation, UserOperationEvent};
+use crate::governance::{DEFAULT_PROPOSAL_THRESHOLD, DEFAULT_QUORUM};
+use crate::helpers::MAX_BPS;
+use crate::maintenance::MaintenancePhase;
+use crate::roles::Role;
+use crate::toggles::{CLAIM_FEATURE, INSTANT_UNSTAKE_FEATURE, STAKE_FEATURE, UNSTAKE_FEATURE};
+use crate::tokens::{ClaimResult, ClaimStatus, TokenAttributes};
+use crate::validators::{ExitPhase, ValidatorExit, ValidatorStatus};
+
+// epochs the delegation contract keeps undelegated EGLD locked
+const DEFAULT_UNBOND_PERIOD: u64 = 10;
 
 #[elrond_wasm::contract]
 pub trait StakeContract:
     elrond_wasm_modules::default_issue_callbacks::DefaultIssueCallbacksModule
+    + elrond_wasm_modules::pause::PauseModule
+    + elrond_wasm_modules::features::FeaturesModule
     + storage::StorageModule
     + callbacks::CallbacksModule
     + events::EventsModule
     + tokens::TokenModule
+    + governance::GovernanceModule
     + helpers::HelpersModule
+    + keeper::KeeperModule
+    + limits::LimitsModule
     + maintenance::MaintenanceModule
+    + oracle::OracleModule
+    + positions::PositionsModule
+    + rate_history::RateHistoryModule
+    + referral::ReferralModule
+    + revenue::RevenueModule
+    + roles::RolesModule
+    + allocation::AllocationModule
+    + allowlist::AllowlistModule
+    + scoring::ScoringModule
+    + timelock::TimelockModule
+    + toggles::TogglesModule
+    + validators::ValidatorsModule
+    + yields::YieldModule
 {
     #[proxy]
     fn delegate_contract(&self, sc_address: ManagedAddress) -> delegate::Proxy<Self::Api>;
@@ -34,23 +78,22 @@ pub trait StakeContract:
         if self.delta_stake().is_empty() {
             self.delta_stake().set(BigInt::from(0))
         };
+        // 1 stEGLD = 1 EGLD until the first rate update
         if self.exchange_rate().is_empty() {
-            self.exchange_rate().set(BigUint::from(1u64))
+            self.exchange_rate().set(BigUint::from(10u64.pow(18)))
         };
-        if self.mapping_index().is_empty() {
-            self.mapping_index().set(1 as usize)
-        };
-        if self.withdraw_mapping_index().is_empty() {
-            self.withdraw_mapping_index().set(1 as usize)
+        if self.exchange_rate_multiplier().is_empty() {
+            self.exchange_rate_multiplier().set(BigUint::from(10u64.pow(18)))
         };
-        if self.rewards_mapping_index().is_empty() {
-            self.rewards_mapping_index().set(1 as usize)
+        if self.unbond_period().is_empty() {
+            self.unbond_period().set(DEFAULT_UNBOND_PERIOD)
         };
-        if self.redelegate_mapping_index().is_empty() {
-            self.redelegate_mapping_index().set(1 as usize)
+        if self.quorum().is_empty() {
+            self.quorum().set(DEFAULT_QUORUM)
         };
-        if self.exchange_rate_multiplier().is_empty() {
-            self.exchange_rate_multiplier().set(BigUint::from(10u64.pow(18)))
+        if self.proposal_threshold().is_empty() {
+            self.proposal_threshold()
+                .set(BigUint::from(DEFAULT_PROPOSAL_THRESHOLD) * BigUint::from(10u64.pow(18)))
         };
     }
 
@@ -59,16 +102,19 @@ pub trait StakeContract:
     #[payable("EGLD")]
     #[endpoint]
     fn stake(&self) {
+        self.require_feature_enabled(STAKE_FEATURE);
+
         let value = self.call_value().egld_value();
 
         require!(&value > &0, "Stake value must be bigger than 0");
 
         let caller = self.blockchain().get_caller();
-        let exchange_rate = self.exchange_rate().get();
-        let exchange_rate_multiplier = self.exchange_rate_multiplier().get();
+
+        self.require_allowlisted(&caller);
+        self.check_stake_limits(&caller, &value);
 
         let st_egld_id = self.staked_egld_id().get();
-        let st_egld_amount = exchange_rate * &value / exchange_rate_multiplier;
+        let st_egld_amount = self.egld_to_st_egld(&value);
 
         let current_total_supply = self.total_token_supply().get();
         let current_delta_stake = self.delta_stake().get();
@@ -79,14 +125,47 @@ pub trait StakeContract:
 
         self.total_token_supply()
             .set(&current_total_supply + &st_egld_amount);
-        self.delta_stake().set(&current_delta_stake + &value);
+        self.liquidity_buffer()
+            .set(&self.liquidity_buffer().get() + &value);
+        self.total_pooled_egld()
+            .set(&self.total_pooled_egld().get() + &value);
+        self.delta_stake().set(&current_delta_stake + &BigInt::from(value.clone()));
+        self.add_referred_deposit(&caller, &value);
+
+        self.stake_event(
+            &caller,
+            self.blockchain().get_block_epoch(),
+            &UserOperationEvent {
+                egld_amount: value,
+                st_egld_amount,
+                uegld_nonce: 0,
+                unlock_epoch: 0,
+                fee: BigUint::zero(),
+                exchange_rate: self.exchange_rate().get(),
+                timestamp: self.blockchain().get_block_timestamp(),
+            },
+        );
     }
 
-    // Receives stEGLD
+    // same as stake, the referrer gets a share of the protocol fee
+    // earned on the stake of the caller
+    #[payable("EGLD")]
+    #[endpoint(stakeWithReferral)]
+    fn stake_with_referral(&self, referrer: ManagedAddress) {
+        let caller = self.blockchain().get_caller();
+
+        self.record_referrer(&caller, &referrer);
+        self.stake();
+    }
+
+    // Receives stEGLD, burns it and sends uEGLD for the EGLD equivalent.
+    // the uEGLD can be claimed after the unbond period
 
     #[payable("*")]
     #[endpoint]
     fn unstake(&self) {
+        self.require_feature_enabled(UNSTAKE_FEATURE);
+
         let (token, _, payment) = self.call_value().single_esdt().into_tuple();
         let st_egld_id = self.staked_egld_id().get();
 
@@ -94,58 +173,212 @@ pub trait StakeContract:
         require!(&payment > &0, "Cannot receive 0 amount");
 
         let caller = self.blockchain().get_caller();
+        self.require_allowlisted(&caller);
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        let egld_amount = self.st_egld_to_egld(&payment);
+
+        require!(&egld_amount > &0, "Unstake amount too small");
+        self.check_unstake_limits(&egld_amount);
+
         let current_total_supply = self.total_token_supply().get();
         let current_delta_stake = self.delta_stake().get();
+        let current_total_unbonding = self.total_unbonding().get();
+        let current_buffer = self.liquidity_buffer().get();
+
+        // deposits that were not delegated yet cover the unstake first,
+        // only the rest has to be undelegated and withdrawn from validators
+        let covered_amount = if current_buffer < egld_amount {
+            current_buffer.clone()
+        } else {
+            egld_amount.clone()
+        };
 
         self.send().esdt_local_burn(&st_egld_id, 0, &payment);
         self.total_token_supply()
             .set(&current_total_supply - &payment);
-        self.delta_stake().set(&current_delta_stake - &payment);
+        self.delta_stake()
+            .set(&current_delta_stake - &BigInt::from(egld_amount.clone()));
+        self.total_unbonding()
+            .set(&current_total_unbonding + &egld_amount);
+        self.liquidity_buffer()
+            .set(&current_buffer - &covered_amount);
+        self.total_pooled_egld()
+            .set(&self.total_pooled_egld().get() - &egld_amount);
+        self.withdrawn_egld()
+            .set(&self.withdrawn_egld().get() + &covered_amount);
+        self.remove_referred_deposit(&caller, &egld_amount);
+
+        let attr = TokenAttributes {
+            unstake_epoch: current_epoch,
+            unlock_epoch: current_epoch + self.unbond_period().get(),
+            egld_amount: egld_amount.clone(),
+        };
 
-        // todo: keep track of unstaking, so user can withdraw later
+        let uegld = self.create_and_send_assets(egld_amount.clone(), &caller, &attr);
+
+        self.unstake_event(
+            &caller,
+            current_epoch,
+            &UserOperationEvent {
+                egld_amount,
+                st_egld_amount: payment,
+                uegld_nonce: uegld.token_nonce,
+                unlock_epoch: attr.unlock_epoch,
+                fee: BigUint::zero(),
+                exchange_rate: self.exchange_rate().get(),
+                timestamp: self.blockchain().get_block_timestamp(),
+            },
+        );
+    }
 
-        /*
-            mint uEGLD based on exchange rate (mint the EGLD equivalent)
-        */
-        let exchange_rate = self.exchange_rate().get();
-        let exchange_rate_multiplier = self.exchange_rate_multiplier().get();
+    // Receives stEGLD and sends EGLD right away out of the liquidity buffer.
+    // the fee stays in the buffer, so it accrues to the stEGLD holders
 
-        let attr = &TokenAttributes {
-            epoch: self.blockchain().get_block_epoch(),
-        };
+    #[payable("*")]
+    #[endpoint(instantUnstake)]
+    fn instant_unstake(&self) {
+        self.require_feature_enabled(INSTANT_UNSTAKE_FEATURE);
 
-        self.stake_value().set(&payment / &exchange_rate * &exchange_rate_multiplier);
+        let (token, _, payment) = self.call_value().single_esdt().into_tuple();
+        let st_egld_id = self.staked_egld_id().get();
 
-        self.create_and_send_assets(&(&payment * & exchange_rate_multiplier) / &exchange_rate, &caller, &attr);
+        require!(&token == &st_egld_id, "Invalid token sent");
+        require!(&payment > &0, "Cannot receive 0 amount");
+
+        let caller = self.blockchain().get_caller();
+        self.require_allowlisted(&caller);
+
+        let egld_amount = self.st_egld_to_egld(&payment);
+        let fee = &egld_amount * &BigUint::from(self.instant_unstake_fee().get()) / MAX_BPS;
+        let egld_to_send = &egld_amount - &fee;
+
+        require!(&egld_to_send > &0, "Unstake amount too small");
+        self.check_unstake_limits(&egld_amount);
+
+        let current_buffer = self.liquidity_buffer().get();
+        require!(
+            &current_buffer >= &egld_to_send,
+            "Not enough liquidity in the buffer"
+        );
+
+        let current_total_supply = self.total_token_supply().get();
+        let current_delta_stake = self.delta_stake().get();
+
+        self.send().esdt_local_burn(&st_egld_id, 0, &payment);
+        self.total_token_supply()
+            .set(&current_total_supply - &payment);
+        self.liquidity_buffer()
+            .set(&current_buffer - &egld_to_send);
+        self.total_pooled_egld()
+            .set(&self.total_pooled_egld().get() - &egld_to_send);
+        self.delta_stake()
+            .set(&current_delta_stake - &BigInt::from(egld_to_send.clone()));
+        self.remove_referred_deposit(&caller, &egld_amount);
+
+        self.send().direct_egld(&caller, &egld_to_send);
+
+        self.instant_unstake_event(
+            &caller,
+            self.blockchain().get_block_epoch(),
+            &UserOperationEvent {
+                egld_amount: egld_to_send,
+                st_egld_amount: payment,
+                uegld_nonce: 0,
+                unlock_epoch: 0,
+                fee,
+                exchange_rate: self.exchange_rate().get(),
+                timestamp: self.blockchain().get_block_timestamp(),
+            },
+        );
     }
 
+    // Receives uEGLD positions, burns the unlocked ones and sends the EGLD owed in one transfer.
+    // positions that can't be claimed yet are sent back
+
     #[payable("*")]
     #[endpoint]
-    fn claim(&self) {
-        let (token, nonce, payment) = self.call_value().single_esdt().into_tuple();
+    fn claim(&self) -> MultiValueEncoded<ClaimResult<Self::Api>> {
+        self.require_feature_enabled(CLAIM_FEATURE);
+
+        let payments = self.call_value().all_esdt_transfers();
         let caller = self.blockchain().get_caller();
-        let sc_address = &self.blockchain().get_sc_address();
         let current_epoch = self.blockchain().get_block_epoch();
         let undelegated_token = self.undelegated_token().get_token_id();
 
-        require!(&token == &undelegated_token, "Invalid token sent");
-        require!(&payment > &0, "Cannot receive 0 amount");
+        require!(payments.len() > 0, "No positions sent");
+
+        let mut withdrawn_egld = self.withdrawn_egld().get();
+        let mut total_claimed = BigUint::zero();
+        let mut waiting_for_withdraw = false;
+        let mut returned_payments = ManagedVec::new();
+        let mut result = MultiValueEncoded::new();
+
+        for payment in payments.iter() {
+            require!(&payment.token_identifier == &undelegated_token, "Invalid token sent");
+            require!(&payment.amount > &0, "Cannot receive 0 amount");
+
+            let attr: TokenAttributes<Self::Api> =
+                self.undelegated_token().get_token_attributes(payment.token_nonce);
+
+            // uEGLD is minted 1:1 with the EGLD owed
+            let status = if current_epoch < attr.unlock_epoch {
+                ClaimStatus::Locked
+            } else if &withdrawn_egld < &payment.amount {
+                ClaimStatus::NotWithdrawn
+            } else {
+                ClaimStatus::Claimed
+            };
+
+            result.push(ClaimResult {
+                nonce: payment.token_nonce,
+                egld_amount: payment.amount.clone(),
+                unlock_epoch: attr.unlock_epoch,
+                status,
+            });
+
+            if status != ClaimStatus::Claimed {
+                waiting_for_withdraw |= status == ClaimStatus::NotWithdrawn;
+                returned_payments.push(payment);
+                continue;
+            }
 
-        let token_info = self
-            .blockchain()
-            .get_esdt_token_data(&sc_address, &token, nonce);
+            self.undelegated_token().nft_burn(payment.token_nonce, &payment.amount);
+            withdrawn_egld -= &payment.amount;
+            total_claimed += &payment.amount;
+
+            self.claim_event(
+                &caller,
+                current_epoch,
+                &UserOperationEvent {
+                    egld_amount: payment.amount,
+                    st_egld_amount: BigUint::zero(),
+                    uegld_nonce: payment.token_nonce,
+                    unlock_epoch: attr.unlock_epoch,
+                    fee: BigUint::zero(),
+                    exchange_rate: self.exchange_rate().get(),
+                    timestamp: self.blockchain().get_block_timestamp(),
+                },
+            );
+        }
 
-        let attr = self
-            .serializer()
-            .top_decode_from_managed_buffer::<TokenAttributes>(&token_info.attributes);
+        if total_claimed == 0 {
+            require!(!waiting_for_withdraw, "Not enough EGLD withdrawn from validators yet");
+            sc_panic!("No position can be claimed yet");
+        }
 
-        let service_fee = self.service_fee().get();
+        let current_total_unbonding = self.total_unbonding().get();
 
-        // check if the epochs required to claim passed
+        self.withdrawn_egld().set(&withdrawn_egld);
+        self.total_unbonding()
+            .set(&current_total_unbonding - &total_claimed);
 
-        if (&attr.epoch - current_epoch) >= 1 {
-            self.send().direct_egld(&caller, &payment)
+        self.send().direct_egld(&caller, &total_claimed);
+        if !returned_payments.is_empty() {
+            self.send().direct_multi(&caller, &returned_payments);
         }
+
+        result
     }
 
     // Admin operations
@@ -154,40 +387,23 @@ pub trait StakeContract:
     // solution for getting how much the SC has staked to validators inside the SC
     // could be replaced with off-chain daemon
 
-    #[only_owner]
     #[endpoint(getStakeAdmin)]
     fn get_stake_admin(&self) {
-        let current_epoch = self.blockchain().get_block_epoch();
-        let mapping_index = self.mapping_index().get();
-        let sc_address = self.blockchain().get_sc_address();
-        let epoch_exists = self.stake_info_started().contains(&current_epoch);
-
-        let rewards_fetched = self.rewards_info_finished().contains(&current_epoch);
-        let redelegate_finished = self.redelegate_finished().contains(&current_epoch);
-        let delegation_finished = self.daily_delegation_finished().contains(&current_epoch);
-
-        require!(rewards_fetched, "must fetch rewards first");
-        require!(delegation_finished, "must complete daily delegation first");
-        require!(redelegate_finished, "must redelegate rewards first");
-
-        require!(
-            !(epoch_exists && mapping_index == 1),
-            "already got stake amount this epoch"
-        );
+        self.require_keeper_or_operator();
+        self.require_maintenance_step_gas();
 
-        if !epoch_exists {
-            self.mapping_index().set(1 as usize);
-            self.stake_info_started().insert(current_epoch.clone());
+        let sc_address = self.blockchain().get_sc_address();
+        let index = match self.take_next_maintenance_index(MaintenancePhase::FetchStake, self.validators().len()) {
+            Some(index) => index,
+            None => return,
         };
-
-        let wanted_address = self.validators().get(mapping_index);
-
-        self.increment_index();
+        let current_epoch = self.blockchain().get_block_epoch();
+        let wanted_address = self.validators().get(index);
 
         self.delegate_contract(wanted_address.clone())
             .getUserActiveStake(sc_address)
             .async_call()
-            .with_callback(StakeContract::callbacks(self).get_stake_callback(current_epoch,wanted_address.clone()))
+            .with_callback(StakeContract::callbacks(self).get_stake_callback(current_epoch, wanted_address.clone(), self.maintenance_resets().get(), self.blockchain().get_caller()))
             .call_and_exit();
     }
 
@@ -197,17 +413,22 @@ pub trait StakeContract:
         &self,
         current_epoch: u64,
         validator: ManagedAddress,
+        resets: u64,
+        keeper: ManagedAddress,
         #[call_result] result: ManagedAsyncCallResult<BigUint>,
     ) {
-        let old_value = self.stake_amounts().get(&current_epoch);
-        let mapping_index = self.mapping_index().get();
-
-        if (mapping_index == 1) {
-            self.stake_info_finished().insert(current_epoch);
+        // a stale result would add to the totals of the restarted phase
+        if !self.is_maintenance_call_current(current_epoch, MaintenancePhase::FetchStake, resets) {
+            return;
         }
 
+        let is_success = matches!(result, ManagedAsyncCallResult::Ok(_));
+        let old_value = self.stake_amounts().get(&current_epoch);
+
         match result {
             ManagedAsyncCallResult::Ok(value) => {
+                self.emit_async_call_success(current_epoch, &validator, DelegationOperation::GetStake, &value);
+
                 self.stake_amounts().insert(
                     current_epoch,
                     match old_value {
@@ -227,60 +448,51 @@ pub trait StakeContract:
                 );
             }
             ManagedAsyncCallResult::Err(err) => {
+                self.emit_async_call_failure(
+                    current_epoch,
+                    &validator,
+                    DelegationOperation::GetStake,
+                    &BigUint::zero(),
+                    err.err_code,
+                    &err.err_msg,
+                );
+
+                // the last known stake of the validator still counts,
+                // leaving it out would move the rate
                 self.stake_amounts().insert(
                     current_epoch,
                     match old_value {
                         Some(n) => n,
                         None => BigUint::from(0u64),
-                    } + BigUint::from(0u64),
-                );
-
-                // todo: check if it can only signal errors for not having any stake
-                self.validator_stake_amount().insert(
-                    validator.clone(),
-                    BigUint::from(0u64)
-                );
-
-                self.validator_stake_amount_clone().insert(
-                    validator.clone(),
-                    BigUint::from(0u64)
+                    } + self.get_validator_stake(&validator),
                 );
             }
 
         }
+
+        self.complete_maintenance_call(current_epoch, MaintenancePhase::FetchStake, resets, self.validators().len(), &keeper, is_success);
     }
 
-    #[only_owner]
     #[endpoint(getRewardsAdmin)]
     fn get_rewards_admin(&self) {
+        self.require_keeper_or_operator();
+        self.require_maintenance_step_gas();
+
         // solution for getting how much the SC has staked to validators inside the SC
         // could be replaced with off-chain daemon
 
-        let current_epoch = self.blockchain().get_block_epoch();
-        let mapping_index = self.rewards_mapping_index().get();
         let sc_address = self.blockchain().get_sc_address();
-
-        // let current_epoch_amounts: _= self.stake_amounts().iter().filter(|amount| amount.epoch == current_epoch).collect::<ManagedVec<StakeAmount<Self::Api>>>();
-        let epoch_exists = self.rewards_info_started().contains(&current_epoch);
-
-        require!(
-            !(epoch_exists && mapping_index == 1),
-            "already got rewards amount this epoch"
-        );
-
-        if !epoch_exists {
-            self.rewards_mapping_index().set(1 as usize);
-            self.rewards_info_started().insert(current_epoch.clone());
+        let index = match self.take_next_maintenance_index(MaintenancePhase::FetchRewards, self.validators().len()) {
+            Some(index) => index,
+            None => return,
         };
+        let current_epoch = self.blockchain().get_block_epoch();
+        let wanted_address = self.validators().get(index);
 
-        let wanted_address = self.validators().get(mapping_index);
-
-        self.increment_index_rewards();
-
-        self.delegate_contract(wanted_address)
+        self.delegate_contract(wanted_address.clone())
             .getClaimableRewards(sc_address)
             .async_call()
-            .with_callback(StakeContract::callbacks(self).get_rewards_callback(current_epoch))
+            .with_callback(StakeContract::callbacks(self).get_rewards_callback(current_epoch, wanted_address, self.maintenance_resets().get(), self.blockchain().get_caller()))
             .call_and_exit();
     }
 
@@ -288,17 +500,22 @@ pub trait StakeContract:
     fn get_rewards_callback(
         &self,
         current_epoch: u64,
+        validator: ManagedAddress,
+        resets: u64,
+        keeper: ManagedAddress,
         #[call_result] result: ManagedAsyncCallResult<BigUint>,
     ) {
-        let old_value = self.rewards_amounts().get(&current_epoch);
-        let mapping_index = self.mapping_index().get();
-
-        if (mapping_index == 1) {
-            self.rewards_info_finished().insert(current_epoch);
+        // a stale result would add to the totals of the restarted phase
+        if !self.is_maintenance_call_current(current_epoch, MaintenancePhase::FetchRewards, resets) {
+            return;
         }
 
+        let is_success = matches!(result, ManagedAsyncCallResult::Ok(_));
+        let old_value = self.rewards_amounts().get(&current_epoch);
+
         match result {
             ManagedAsyncCallResult::Ok(value) => {
+                self.emit_async_call_success(current_epoch, &validator, DelegationOperation::GetRewards, &value);
 
                 self.rewards_amounts().insert(
                     current_epoch,
@@ -308,9 +525,18 @@ pub trait StakeContract:
                     } + value.clone(),
                 );
 
-                self.update_protocol_revenue(&current_epoch);
+                self.record_validator_rewards(&validator, current_epoch, &value);
             }
             ManagedAsyncCallResult::Err(err) => {
+                self.emit_async_call_failure(
+                    current_epoch,
+                    &validator,
+                    DelegationOperation::GetRewards,
+                    &BigUint::zero(),
+                    err.err_code,
+                    &err.err_msg,
+                );
+
                 self.rewards_amounts().insert(
                     current_epoch,
                     match old_value {
@@ -318,10 +544,12 @@ pub trait StakeContract:
                         None => BigUint::from(0u64),
                     } + BigUint::from(0u64)
                 );
-
-                self.update_protocol_revenue(&current_epoch);
             }
         }
+
+        if self.complete_maintenance_call(current_epoch, MaintenancePhase::FetchRewards, resets, self.validators().len(), &keeper, is_success) {
+            self.update_protocol_revenue(&current_epoch);
+        }
     }
 
     #[callback]
@@ -329,80 +557,164 @@ pub trait StakeContract:
         &self,
         current_epoch: u64,
         address:ManagedAddress,
+        amount: BigUint,
+        from_plan: bool,
+        resets: u64,
+        keeper: ManagedAddress,
         #[call_result] result: ManagedAsyncCallResult<()>,
     ) {
+        let is_success = matches!(result, ManagedAsyncCallResult::Ok(_));
+
         match result {
             ManagedAsyncCallResult::Ok(()) => {
-                // perfect scenario, daily delegation is finished
-                self.delta_stake().clear();
-                self.daily_delegation_finished().insert(current_epoch);
+                self.emit_async_call_success(current_epoch, &address, DelegationOperation::Delegate, &amount);
+
+                // the amount already left the buffer when the call was sent
+                let delta_stake = self.delta_stake().get();
+                let validator_stake = self.get_validator_stake(&address);
+
+                self.delta_stake().set(&delta_stake - &BigInt::from(amount.clone()));
+                self.validator_stake_amount().insert(address, validator_stake + amount);
             }
             ManagedAsyncCallResult::Err(err) => {
-                self.validator_stake_amount_clone().remove(&address);
+                // the EGLD is sent back to the buffer and stays in delta_stake for the next epoch
+                self.liquidity_buffer().update(|buffer| *buffer += &amount);
+                self.emit_async_call_failure(
+                    current_epoch,
+                    &address,
+                    DelegationOperation::Delegate,
+                    &amount,
+                    err.err_code,
+                    &err.err_msg,
+                );
             }
         }
+
+        // manual calls are not part of the daily delegation
+        if from_plan {
+            self.complete_maintenance_call(current_epoch, MaintenancePhase::Delegate, resets, self.delegation_plan().len(), &keeper, is_success);
+        }
     }
 
-    #[only_owner]
+    #[callback]
+    fn undelegation_callback(
+        &self,
+        current_epoch: u64,
+        address:ManagedAddress,
+        amount: BigUint,
+        from_plan: bool,
+        resets: u64,
+        keeper: ManagedAddress,
+        #[call_result] result: ManagedAsyncCallResult<()>,
+    ) {
+        let is_success = matches!(result, ManagedAsyncCallResult::Ok(_));
+
+        match result {
+            ManagedAsyncCallResult::Ok(()) => {
+                self.emit_async_call_success(current_epoch, &address, DelegationOperation::Undelegate, &amount);
+
+                let delta_stake = self.delta_stake().get();
+                let pending_undelegation = self.pending_undelegation().get();
+                let validator_stake = self.get_validator_stake(&address);
+
+                self.pending_undelegation().set(&pending_undelegation + &amount);
+                self.delta_stake().set(&delta_stake + &BigInt::from(amount.clone()));
+                self.validator_stake_amount().insert(
+                    address,
+                    if validator_stake > amount {
+                        validator_stake - amount
+                    } else {
+                        BigUint::zero()
+                    },
+                );
+            }
+            ManagedAsyncCallResult::Err(err) => {
+                // the amount stays in delta_stake for the next epoch
+                self.emit_async_call_failure(
+                    current_epoch,
+                    &address,
+                    DelegationOperation::Undelegate,
+                    &amount,
+                    err.err_code,
+                    &err.err_msg,
+                );
+            }
+        }
+
+        // manual calls are not part of the daily delegation
+        if from_plan {
+            self.complete_maintenance_call(current_epoch, MaintenancePhase::Delegate, resets, self.delegation_plan().len(), &keeper, is_success);
+        }
+    }
+
+    // manual corrections, only towards registered validators.
+    // delegations are taken out of the liquidity buffer
     #[endpoint(delegate_direct)]
     fn delegate_direct(&self, address: ManagedAddress, amount: BigUint) {
+        self.require_role(Role::Operator);
+
+        let info = self.require_validator_registered(&address);
+        require!(info.status == ValidatorStatus::Active, "Validator is not active");
+        require!(
+            amount <= self.liquidity_buffer().get(),
+            "Not enough liquidity in the buffer"
+        );
+
+        self.send_delegation(address, amount, false);
+    }
+
+    #[endpoint]
+    fn undelegate_direct(&self, address: ManagedAddress, amount: &BigUint) {
+        self.require_role(Role::Operator);
+
+        self.require_validator_registered(&address);
+
+        self.send_undelegation(address, amount.clone(), false);
+    }
+
+    fn send_delegation(&self, address: ManagedAddress, amount: BigUint, from_plan: bool) {
         // directly delegates to the contract specified.
+        // the amount leaves the buffer right away, so unstakes sent before the callback
+        // can't use it
         let current_epoch = self.blockchain().get_block_epoch();
 
+        self.liquidity_buffer().update(|buffer| *buffer -= &amount);
+
         self.delegate_contract(address.clone() )
-            .delegate(EgldOrEsdtTokenIdentifier::egld(), amount)
-            .async_call().with_callback(StakeContract::callbacks(self).delegation_callback(current_epoch, address))
+            .delegate(EgldOrEsdtTokenIdentifier::egld(), amount.clone())
+            .async_call().with_callback(StakeContract::callbacks(self).delegation_callback(current_epoch, address, amount, from_plan, self.maintenance_resets().get(), self.blockchain().get_caller()))
             .call_and_exit();
     }
 
-    #[only_owner]
-    #[endpoint]
-    fn undelegate_direct(&self, address: ManagedAddress, amount: &BigUint) {
+    fn send_undelegation(&self, address: ManagedAddress, amount: BigUint, from_plan: bool) {
         // directly undelegates from the contract specified.
         let current_epoch = self.blockchain().get_block_epoch();
 
         self.delegate_contract(address.clone())
-            .unDelegate(amount)
-            .async_call().with_callback(StakeContract::callbacks(self).delegation_callback(current_epoch, address))
+            .unDelegate(&amount)
+            .async_call().with_callback(StakeContract::callbacks(self).undelegation_callback(current_epoch, address, amount, from_plan, self.maintenance_resets().get(), self.blockchain().get_caller()))
             .call_and_exit();
     }
 
-
     // redelegates rewards at each validator.
     // should be done after computing rewards
 
-    #[only_owner]
     #[endpoint(redelegateAdmin)]
     fn redelegateAdmin(&self) {
-        let mapping_index = self.redelegate_mapping_index().get();
-        let current_epoch = self.blockchain().get_block_epoch();
-        let validators = self.validators();
-        let epoch_exists = self.redelegate_started().contains(&current_epoch);
-        
-        let is_rewards_info_finished = self.rewards_info_finished().contains(&current_epoch);
-        let wanted_address = self.validators().get(mapping_index);
+        self.require_keeper_or_operator();
+        self.require_maintenance_step_gas();
 
-        require!(
-            is_rewards_info_finished,
-            "must get rewards first"
-        );
-        
-        require!(
-            !(epoch_exists && mapping_index == 1),
-            "already redelegated"
-        );
-
-        if !epoch_exists {
-            self.mapping_index().set(1 as usize);
-            self.redelegate_started().insert(current_epoch.clone());
+        let index = match self.take_next_maintenance_index(MaintenancePhase::Redelegate, self.validators().len()) {
+            Some(index) => index,
+            None => return,
         };
+        let current_epoch = self.blockchain().get_block_epoch();
+        let wanted_address = self.validators().get(index);
 
-        self.increment_index_redelegate();
-
-        self.delegate_contract(wanted_address)
+        self.delegate_contract(wanted_address.clone())
             .reDelegateRewards()
             .async_call()
-            .with_callback(StakeContract::callbacks(self).redelegate_callback(current_epoch))
+            .with_callback(StakeContract::callbacks(self).redelegate_callback(current_epoch, wanted_address, self.maintenance_resets().get(), self.blockchain().get_caller()))
             .call_and_exit();
     }
 
@@ -410,129 +722,285 @@ pub trait StakeContract:
     fn redelegate_callback(
         &self,
         current_epoch: u64,
+        validator: ManagedAddress,
+        resets: u64,
+        keeper: ManagedAddress,
         #[call_result] result: ManagedAsyncCallResult<()>,
     ) {
-        let mapping_index = self.redelegate_mapping_index().get();
-        
-        if &mapping_index == &(1 as usize) {
-            self.redelegate_finished().insert(current_epoch);
+        let is_success = matches!(result, ManagedAsyncCallResult::Ok(_));
+
+        match result {
+            ManagedAsyncCallResult::Ok(()) => {
+                self.emit_async_call_success(current_epoch, &validator, DelegationOperation::Redelegate, &BigUint::zero());
+            }
+            ManagedAsyncCallResult::Err(err) => {
+                self.emit_async_call_failure(
+                    current_epoch,
+                    &validator,
+                    DelegationOperation::Redelegate,
+                    &BigUint::zero(),
+                    err.err_code,
+                    &err.err_msg,
+                );
+            }
         }
+
+        self.complete_maintenance_call(current_epoch, MaintenancePhase::Redelegate, resets, self.validators().len(), &keeper, is_success);
     }
 
-    #[only_owner]
     #[endpoint(withdrawAdmin)]
     fn withdraw_admin(&self) {
-        // solution for getting how much the SC has staked to validators inside the SC
-        // could be replaced with off-chain daemon
+        self.require_keeper_or_operator();
+        self.require_maintenance_step_gas();
 
+        let index = match self.take_next_maintenance_index(MaintenancePhase::Withdraw, self.validators().len()) {
+            Some(index) => index,
+            None => return,
+        };
         let current_epoch = self.blockchain().get_block_epoch();
-        let mapping_index = self.withdraw_mapping_index().get();
-        let sc_address = self.blockchain().get_sc_address();
-    
-        let epoch_exists = self.withdraw_started().contains(&current_epoch);
+        let wanted_address = self.validators().get(index);
+
+        self.delegate_contract(wanted_address.clone())
+            .withdraw()
+            .async_call()
+            .with_callback(StakeContract::callbacks(self).withdraw_callback(current_epoch, wanted_address.clone(), self.maintenance_resets().get(), self.blockchain().get_caller()))
+            .call_and_exit();
+    }
+
+    #[callback]
+    fn withdraw_callback(
+        &self,
+        current_epoch: u64,
+        validator: ManagedAddress,
+        resets: u64,
+        keeper: ManagedAddress,
+        #[call_result] result: ManagedAsyncCallResult<()>,
+    ) {
+        let is_success = matches!(result, ManagedAsyncCallResult::Ok(_));
+
+        match result {
+            ManagedAsyncCallResult::Ok(()) => {
+                let withdrawn_amount = self.call_value().egld_value();
+
+                self.emit_async_call_success(current_epoch, &validator, DelegationOperation::Withdraw, &withdrawn_amount);
+                self.handle_withdrawn_egld(&validator, &withdrawn_amount);
+            }
+            ManagedAsyncCallResult::Err(err) => {
+                self.emit_async_call_failure(
+                    current_epoch,
+                    &validator,
+                    DelegationOperation::Withdraw,
+                    &BigUint::zero(),
+                    err.err_code,
+                    &err.err_msg,
+                );
+            }
+        }
+
+        self.complete_maintenance_call(current_epoch, MaintenancePhase::Withdraw, resets, self.validators().len(), &keeper, is_success);
+    }
+
+    // Validator exit
+    // - moves the whole stake of a retired validator to the active ones:
+    //   undelegate everything, withdraw once unbonded, then the EGLD goes
+    //   back to the buffer and the daily delegation spreads it by weight
+
+    #[endpoint(startValidatorExit)]
+    fn start_validator_exit(&self, address: ManagedAddress) {
+        self.require_role(Role::Operator);
+
+        let info = self.require_validator_registered(&address);
+        let current_epoch = self.blockchain().get_block_epoch();
+        let stake = self.get_validator_stake(&address);
 
         require!(
-            !(epoch_exists && mapping_index == 1),
-            "already withdrew this epoch"
+            info.status == ValidatorStatus::Exiting,
+            "Validator must be retired first"
         );
+        require!(
+            self.validator_exit(&address).is_empty()
+                || self.validator_exit(&address).get().phase == ExitPhase::Completed,
+            "Validator exit already in progress"
+        );
+        require!(stake > 0, "Validator has no stake to migrate");
 
-        if !epoch_exists {
-            self.withdraw_mapping_index().set(1 as usize);
-            self.withdraw_started().insert(current_epoch.clone());
-        };
+        self.delegate_contract(address.clone())
+            .unDelegate(&stake)
+            .async_call()
+            .with_callback(StakeContract::callbacks(self).exit_undelegate_callback(current_epoch, address, stake))
+            .call_and_exit();
+    }
 
-        let wanted_address = self.validators().get(mapping_index);
+    #[callback]
+    fn exit_undelegate_callback(
+        &self,
+        current_epoch: u64,
+        address: ManagedAddress,
+        amount: BigUint,
+        #[call_result] result: ManagedAsyncCallResult<()>,
+    ) {
+        match result {
+            ManagedAsyncCallResult::Ok(()) => {
+                self.emit_async_call_success(current_epoch, &address, DelegationOperation::ExitUndelegate, &amount);
+
+                let pending_undelegation = self.pending_undelegation().get();
+
+                self.pending_undelegation().set(&pending_undelegation + &amount);
+                self.validator_stake_amount().insert(address.clone(), BigUint::zero());
+                self.validator_exit(&address).set(&ValidatorExit {
+                    validator: address.clone(),
+                    phase: ExitPhase::Unbonding,
+                    amount,
+                    withdrawn_amount: BigUint::zero(),
+                    undelegate_epoch: current_epoch,
+                    unlock_epoch: current_epoch + self.unbond_period().get(),
+                });
+            }
+            ManagedAsyncCallResult::Err(err) => {
+                // nothing changed, the exit can be started again
+                self.emit_async_call_failure(
+                    current_epoch,
+                    &address,
+                    DelegationOperation::ExitUndelegate,
+                    &amount,
+                    err.err_code,
+                    &err.err_msg,
+                );
+            }
+        }
+    }
+
+    #[endpoint(withdrawValidatorExit)]
+    fn withdraw_validator_exit(&self, address: ManagedAddress) {
+        self.require_role(Role::Operator);
+
+        let current_epoch = self.blockchain().get_block_epoch();
+
+        require!(
+            !self.validator_exit(&address).is_empty(),
+            "No exit in progress for this validator"
+        );
 
-        self.increment_index_withdraw();
+        let exit = self.validator_exit(&address).get();
+
+        require!(exit.phase == ExitPhase::Unbonding, "Validator exit is not unbonding");
+        require!(
+            current_epoch >= exit.unlock_epoch,
+            "Validator exit is still unbonding"
+        );
 
-        self.delegate_contract(wanted_address)
+        self.delegate_contract(address.clone())
             .withdraw()
             .async_call()
-            .with_callback(StakeContract::callbacks(self).withdraw_callback(current_epoch))
+            .with_callback(StakeContract::callbacks(self).exit_withdraw_callback(current_epoch, address))
             .call_and_exit();
     }
 
     #[callback]
-    fn withdraw_callback(
+    fn exit_withdraw_callback(
         &self,
         current_epoch: u64,
+        address: ManagedAddress,
         #[call_result] result: ManagedAsyncCallResult<()>,
     ) {
-        let mapping_index = self.withdraw_mapping_index().get();
-
-        // if mapping index is 1, it means it was the last one
+        match result {
+            ManagedAsyncCallResult::Ok(()) => {
+                let withdrawn_amount = self.call_value().egld_value();
 
-        if &mapping_index == &(1 as usize) {
-            self.withdraw_finished().insert(current_epoch);
+                self.emit_async_call_success(current_epoch, &address, DelegationOperation::ExitWithdraw, &withdrawn_amount);
+                self.handle_withdrawn_egld(&address, &withdrawn_amount);
+            }
+            ManagedAsyncCallResult::Err(err) => {
+                self.emit_async_call_failure(
+                    current_epoch,
+                    &address,
+                    DelegationOperation::ExitWithdraw,
+                    &BigUint::zero(),
+                    err.err_code,
+                    &err.err_msg,
+                );
+            }
         }
     }
 
-    #[only_owner]
-    #[endpoint]
-    fn push_validators(&self, address: &ManagedAddress) {
-        // todo: if it isn't on the list, add it to stake vector and rewards vector as well
-        self.validators().push(address);
-    }
-
     // todo: rename to delegateAdmin
+
+    // first call of the epoch computes the delegation plan from delta_stake,
+    // each call (including the first) then sends one item of the plan
     #[endpoint(dailyDelegation)]
     fn daily_delegation(&self) {
-        let mut smallest = BigUint::from(0u64);
-        let mut biggest = BigUint::from(0u64);
-        let current_epoch = self.blockchain().get_block_epoch();
-        let delta_stake = self.delta_stake().get();
+        self.require_keeper_or_operator();
+        self.require_maintenance_step_gas();
 
-        let validators= self.validator_stake_amount_clone();
+        let state = self.require_maintenance_phase(MaintenancePhase::Delegate);
+
+        if state.next_index == 1 && state.pending_callbacks == 0 {
+            self.build_delegation_plan();
+        }
+
+        let index = match self.take_next_maintenance_index(MaintenancePhase::Delegate, self.delegation_plan().len()) {
+            Some(index) => index,
+            None => return,
+        };
 
+        let item = self.delegation_plan().get(index);
+        let is_undelegation = self.delegation_plan_is_undelegation().get();
 
-        let is_smaller_than_minimum = (delta_stake > 0 && delta_stake < BigInt::from(10_i64.pow(18)));
-        
-        if (is_smaller_than_minimum || delta_stake == 0) {
-            self.daily_delegation_finished().insert(current_epoch.clone());
+        if is_undelegation {
+            self.send_undelegation(item.validator, item.amount, true);
+            return;
         }
 
-        if delta_stake > 0 {
-            // set smallest as 1st entry
-            for validator in validators.values() {
-                smallest = validator;
-                break;
-            }
+        // unstakes since the plan was built can have used the buffer,
+        // the item is capped to what is still above the buffer target
+        let buffer_surplus = self.get_buffer_surplus();
+        let amount = if item.amount < buffer_surplus {
+            item.amount
+        } else {
+            buffer_surplus
+        };
 
-             // find the one with the least amount [smallest]
-            for validator in validators.values() {
-                if validator < smallest{
-                    smallest = validator; //find smallest
-                }
-            }
+        if amount == 0 {
+            self.complete_maintenance_call(
+                state.epoch,
+                MaintenancePhase::Delegate,
+                self.maintenance_resets().get(),
+                self.delegation_plan().len(),
+                &self.blockchain().get_caller(),
+                false,
+            );
+            return;
+        }
 
-            for validator in validators.iter() {
-                if validator.1 == smallest {
-                   self.delegate_direct(validator.0 , delta_stake.magnitude());
-                   break;
-                } 
-            }
+        self.send_delegation(item.validator, amount, true);
+    }
 
-        } else if delta_stake < 0 {
-            // set biggest as 1st entry
-            for validator in validators.values() {
-                biggest = validator;
-                break;
-            }
-        
-            //find the one with the biggest amount [biggest]
-            for validator in validators.values() {
-                if validator > biggest{
-                    biggest = validator; //find biggest
-                } 
-            }
-            
-            for validator in validators.iter() {
-                if validator.1 == biggest {
-                   self.undelegate_direct(validator.0 , &delta_stake.magnitude());
-                   break;
-                } 
-            }
+    fn build_delegation_plan(&self) {
+        let delta_stake = self.delta_stake().get();
+
+        // keep the buffer target in the contract for instant unstakes,
+        // only the surplus gets delegated
+        let buffer_surplus = self.get_buffer_surplus();
+        let amount_to_delegate = if delta_stake > 0 && delta_stake.magnitude() < buffer_surplus {
+            delta_stake.magnitude()
+        } else if delta_stake > 0 {
+            buffer_surplus
+        } else {
+            BigUint::zero()
+        };
+
+        let plan = if delta_stake < 0 {
+            self.compute_undelegation_plan(&delta_stake.magnitude())
+        } else {
+            self.compute_delegation_plan(&amount_to_delegate)
+        };
+
+        self.delegation_plan().clear();
+        for item in plan.iter() {
+            self.delegation_plan().push(&item);
         }
+
+        self.delegation_plan_is_undelegation().set(delta_stake < 0);
     }
 
-}
+}
\ No newline at end of file
@@ -1,67 +1,362 @@
 use elrond_wasm::api::HandleConstraints;
 
+use crate::events::ExchangeRateUpdatedEvent;
+use crate::helpers::MAX_BPS;
+use crate::roles::Role;
+
+// how much an update or a report can move the exchange rate, in basis points
+pub const DEFAULT_MAX_RATE_CHANGE: u64 = 100;
+
 elrond_wasm::imports!();
 elrond_wasm::derive_imports!();
 
+// the daily maintenance phases, in the order they run
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, Copy, PartialEq)]
+pub enum MaintenancePhase {
+    FetchRewards,
+    Redelegate,
+    Delegate,
+    FetchStake,
+    Withdraw,
+    UpdateExchangeRate,
+    Completed,
+}
+
+impl MaintenancePhase {
+    pub fn next(&self) -> MaintenancePhase {
+        match self {
+            MaintenancePhase::FetchRewards => MaintenancePhase::Redelegate,
+            MaintenancePhase::Redelegate => MaintenancePhase::Delegate,
+            MaintenancePhase::Delegate => MaintenancePhase::FetchStake,
+            MaintenancePhase::FetchStake => MaintenancePhase::Withdraw,
+            MaintenancePhase::Withdraw => MaintenancePhase::UpdateExchangeRate,
+            MaintenancePhase::UpdateExchangeRate => MaintenancePhase::Completed,
+            MaintenancePhase::Completed => MaintenancePhase::Completed,
+        }
+    }
+
+    // returned when a later phase is called while this one is still running
+    pub fn pending_error(&self) -> &'static str {
+        match self {
+            MaintenancePhase::FetchRewards => "must fetch rewards first",
+            MaintenancePhase::Redelegate => "must redelegate rewards first",
+            MaintenancePhase::Delegate => "must complete daily delegation first",
+            MaintenancePhase::FetchStake => "must fetch stake first",
+            MaintenancePhase::Withdraw => "must withdraw first",
+            MaintenancePhase::UpdateExchangeRate => "must update exchange rate first",
+            MaintenancePhase::Completed => "maintenance already completed this epoch",
+        }
+    }
+
+    // returned when this phase is called again after it finished
+    pub fn finished_error(&self) -> &'static str {
+        match self {
+            MaintenancePhase::FetchRewards => "already got rewards amount this epoch",
+            MaintenancePhase::Redelegate => "already redelegated",
+            MaintenancePhase::Delegate => "daily delegation already finished this epoch",
+            MaintenancePhase::FetchStake => "already got stake amount this epoch",
+            MaintenancePhase::Withdraw => "already withdrew this epoch",
+            MaintenancePhase::UpdateExchangeRate => "Exchange rate already updated for this epoch",
+            MaintenancePhase::Completed => "maintenance already completed this epoch",
+        }
+    }
+}
+
+// progress of the daily maintenance.
+// each phase sends one async call per transaction (next_index is 1-based, like the VecMappers)
+// and only moves to the next phase once every callback came back
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct MaintenanceState {
+    pub epoch: u64,
+    pub phase: MaintenancePhase,
+    pub next_index: usize,
+    pub pending_callbacks: usize,
+}
+
 #[elrond_wasm::module]
 pub trait MaintenanceModule: 
-    crate::storage::StorageModule 
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    + crate::helpers::HelpersModule
+    + crate::keeper::KeeperModule
+    + crate::rate_history::RateHistoryModule
     {
-        
-        // the endpoint for distributing the protocol fees
-        // protocol rewards are in stEGLD.
-        // this endpoint mints & sends the revenue to the contract owner
-        #[endpoint(distributeProtocolRevenue)]
-        fn distribute_protocol_revenue(&self) {
-            let st_egld_id = self.staked_egld_id().get();
-            let sc_owner = self.blockchain().get_owner_address();
-            let protocol_revenue = self.protocol_revenue().get();
-            
-            let exchange_rate = self.exchange_rate().get();
-            let exchange_rate_multiplier = self.exchange_rate_multiplier().get();
 
-            let amount_to_send = &protocol_revenue * &exchange_rate / exchange_rate_multiplier;
-            let current_total_supply = self.total_token_supply().get();
+        #[view(getMaintenanceState)]
+        fn get_maintenance_state(&self) -> MaintenanceState {
+            let current_epoch = self.blockchain().get_block_epoch();
 
-            self.send().esdt_local_mint(&st_egld_id, 0, &amount_to_send);
-            self.send().direct_esdt(&sc_owner, &st_egld_id, 0, &amount_to_send);
+            if !self.maintenance_state().is_empty() {
+                let state = self.maintenance_state().get();
+                if state.epoch == current_epoch {
+                    return state;
+                }
+            }
 
-            self.total_token_supply()
-                .set(&current_total_supply + &amount_to_send);
+            // every epoch starts a new cycle
+            MaintenanceState {
+                epoch: current_epoch,
+                phase: MaintenancePhase::FetchRewards,
+                next_index: 1,
+                pending_callbacks: 0,
+            }
+        }
 
-            self.protocol_revenue().set(BigUint::from(0u64));
+        #[view(getMaintenancePhase)]
+        fn get_maintenance_phase(&self) -> MaintenancePhase {
+            self.get_maintenance_state().phase
         }
 
-       
+        // recovery in case a callback never comes back
+        #[endpoint(resetMaintenancePhase)]
+        fn reset_maintenance_phase(&self, phase: MaintenancePhase) {
+            self.require_role(Role::Admin);
+
+            let mut state = self.get_maintenance_state();
+
+            state.phase = phase;
+            state.next_index = 1;
+            state.pending_callbacks = 0;
+            self.maintenance_state().set(&state);
+            self.maintenance_resets().update(|resets| *resets += 1);
+        }
+
+        // State machine
+
+        fn require_maintenance_phase(&self, phase: MaintenancePhase) -> MaintenanceState {
+            let state = self.get_maintenance_state();
+
+            if state.phase != phase {
+                if (state.phase as u8) < (phase as u8) {
+                    sc_panic!(state.phase.pending_error());
+                } else {
+                    sc_panic!(phase.finished_error());
+                }
+            }
+
+            state
+        }
+
+        // returns the next item (validator or plan entry) to send the async call to,
+        // or None if the phase has nothing to do and was skipped
+        fn take_next_maintenance_index(&self, phase: MaintenancePhase, total: usize) -> Option<usize> {
+            let mut state = self.require_maintenance_phase(phase);
+
+            if state.next_index == 1 && state.pending_callbacks == 0 {
+                self.maintenance_phase_started_event(state.epoch, phase);
+
+                // the fetch callbacks add up the totals of the epoch, a restarted phase
+                // starts them over
+                match phase {
+                    MaintenancePhase::FetchRewards => {
+                        self.rewards_amounts().remove(&state.epoch);
+                    },
+                    MaintenancePhase::FetchStake => {
+                        self.stake_amounts().remove(&state.epoch);
+                    },
+                    _ => {},
+                }
+
+                if total == 0 {
+                    self.finish_maintenance_phase(&mut state);
+                    self.maintenance_state().set(&state);
+                    return None;
+                }
+            }
+
+            require!(
+                state.next_index <= total,
+                "all calls of this phase were sent, waiting for callbacks"
+            );
+
+            let index = state.next_index;
+
+            state.next_index += 1;
+            state.pending_callbacks += 1;
+            self.maintenance_state().set(&state);
+
+            Some(index)
+        }
+
+        // false for the callbacks of an older epoch or phase, or of calls sent before a reset
+        fn is_maintenance_call_current(&self, epoch: u64, phase: MaintenancePhase, resets: u64) -> bool {
+            if self.maintenance_state().is_empty() || resets != self.maintenance_resets().get() {
+                return false;
+            }
+
+            let state = self.maintenance_state().get();
+
+            state.epoch == epoch && state.phase == phase && state.pending_callbacks > 0
+        }
+
+        // called from the callbacks, returns true if it was the last one of the phase.
+        // stale callbacks don't touch the state.
+        // the keeper that sent the call earns the bounty only if it succeeded
+        fn complete_maintenance_call(
+            &self,
+            epoch: u64,
+            phase: MaintenancePhase,
+            resets: u64,
+            total: usize,
+            keeper: &ManagedAddress,
+            is_success: bool,
+        ) -> bool {
+            if !self.is_maintenance_call_current(epoch, phase, resets) {
+                return false;
+            }
+
+            let mut state = self.maintenance_state().get();
+
+            state.pending_callbacks -= 1;
+
+            if is_success {
+                self.reward_keeper(epoch, phase, keeper);
+            }
+
+            let is_finished = state.next_index > total && state.pending_callbacks == 0;
+            if is_finished {
+                self.finish_maintenance_phase(&mut state);
+            }
+
+            self.maintenance_state().set(&state);
+
+            is_finished
+        }
+
+        fn finish_maintenance_phase(&self, state: &mut MaintenanceState) {
+            self.maintenance_phase_finished_event(state.epoch, state.phase);
+
+            state.phase = state.phase.next();
+            if self.is_epoch_reported(state.epoch) {
+                while state.phase == MaintenancePhase::FetchStake
+                    || state.phase == MaintenancePhase::UpdateExchangeRate
+                {
+                    state.phase = state.phase.next();
+                }
+            }
+            state.next_index = 1;
+            state.pending_callbacks = 0;
+        }
+        
         #[endpoint(updateExchangeRate)]
         fn update_exchange_rate(&self) {
-            let current_epoch = self.blockchain().get_block_epoch(); 
-            let already_updated = self.exchange_rate_update_finished().contains(&current_epoch);
+            let mut state = self.require_maintenance_phase(MaintenancePhase::UpdateExchangeRate);
+            let current_epoch = state.epoch;
+
+            let active_stake = match self.stake_amounts().get(&current_epoch) {
+                Some(n) => n,
+                None => BigUint::zero(),
+            };
+
+            self.maintenance_phase_started_event(current_epoch, MaintenancePhase::UpdateExchangeRate);
+
+            let total_pooled_egld = self.get_total_pooled_egld(active_stake);
+            let new_rate = self.compute_exchange_rate(&total_pooled_egld);
+            if let Some(rate) = &new_rate {
+                self.require_rate_change_within_bounds(rate);
+            }
+            self.set_exchange_rate(current_epoch, total_pooled_egld, new_rate);
+
+            self.finish_maintenance_phase(&mut state);
+            self.maintenance_state().set(&state);
+            self.reward_keeper(current_epoch, MaintenancePhase::UpdateExchangeRate, &self.blockchain().get_caller());
+        }
+
+        // Exchange rate
+
+        #[storage_mapper("max_rate_change")]
+        fn max_rate_change(&self) -> SingleValueMapper<u64>;
+
+        #[endpoint(setMaxRateChange)]
+        fn set_max_rate_change(&self, max_change: u64) {
+            self.require_role(Role::Admin);
 
-            require!(!already_updated, "Exchange rate already updated for this epoch");
+            require!(max_change <= MAX_BPS, "Max rate change cannot exceed 100%");
+            self.max_rate_change().set(max_change);
+        }
+
+        #[view(getMaxRateChange)]
+        fn get_max_rate_change(&self) -> u64 {
+            if self.max_rate_change().is_empty() {
+                DEFAULT_MAX_RATE_CHANGE
+            } else {
+                self.max_rate_change().get()
+            }
+        }
 
-            // todo: make this only work with amounts
-            // this approach works even if the operations are not completed
-            let stake_info_finished = self.stake_info_finished().contains(&current_epoch);
-            let rewards_info_finished = self.rewards_info_finished().contains(&current_epoch);
-            let withdraw_finished = self.withdraw_finished().contains(&current_epoch);
+        // compared to the rate of the previous update
+        fn require_rate_change_within_bounds(&self, new_rate: &BigUint) {
+            let old_rate = self.exchange_rate().get();
+            let change = if new_rate > &old_rate {
+                new_rate - &old_rate
+            } else {
+                &old_rate - new_rate
+            };
 
             require!(
-                stake_info_finished && rewards_info_finished && withdraw_finished,
-                "All operations must be finished before updating exchange rate"
+                change * BigUint::from(MAX_BPS) <= old_rate * BigUint::from(self.get_max_rate_change()),
+                "Exchange rate change exceeds the allowed bound"
             );
+        }
+
+        // everything the contract holds or is owed, minus what belongs to uEGLD holders
+        fn get_total_pooled_egld(&self, active_stake: BigUint) -> BigUint {
+            let total_assets = active_stake
+                + self.liquidity_buffer().get()
+                + self.pending_undelegation().get()
+                + self.withdrawn_egld().get();
+            let liabilities = self.total_unbonding().get();
 
-            let stake_value = self.stake_amounts().get(&current_epoch);
+            if total_assets > liabilities {
+                total_assets - liabilities
+            } else {
+                BigUint::zero()
+            }
+        }
+
+        // None while there is no supply or nothing backs it, the rate stays as it is
+        fn compute_exchange_rate(&self, total_pooled_egld: &BigUint) -> Option<BigUint> {
             let total_token_supply = self.total_token_supply().get();
-            let exchange_rate_multiplier = self.exchange_rate_multiplier().get();
-    
-            self.exchange_rate().set((total_token_supply * exchange_rate_multiplier) / 
-               (match stake_value {
-                    Some(n) => if n > 0 { n } else { BigUint::from(1u64) },
-                    None => BigUint::from(1u64),
-                })
-            );
 
-            self.exchange_rate_update_finished().insert(current_epoch);
-        }      
+            // the fees not minted yet don't back the current supply
+            let unminted_fees = self.unminted_fees().get();
+            let backing_egld = if total_pooled_egld > &unminted_fees {
+                total_pooled_egld - &unminted_fees
+            } else {
+                BigUint::zero()
+            };
+
+            if total_token_supply == 0 || backing_egld == 0 {
+                return None;
+            }
+
+            Some(total_token_supply * self.exchange_rate_multiplier().get() / backing_egld)
+        }
+
+        fn set_exchange_rate(&self, epoch: u64, total_pooled_egld: BigUint, new_rate: Option<BigUint>) {
+            self.total_pooled_egld().set(&total_pooled_egld);
+
+            if let Some(new_rate) = new_rate {
+                let old_rate = self.exchange_rate().get();
+
+                self.exchange_rate().set(&new_rate);
+                self.exchange_rate_updated_event(
+                    epoch,
+                    &ExchangeRateUpdatedEvent {
+                        old_rate,
+                        new_rate,
+                        total_pooled_egld,
+                        total_token_supply: self.total_token_supply().get(),
+                    },
+                );
+            }
+
+            self.record_exchange_rate(epoch, &self.exchange_rate().get());
+        }
+
+        #[inline]
+        fn is_epoch_reported(&self, epoch: u64) -> bool {
+            !self.last_report_epoch().is_empty() && self.last_report_epoch().get() == epoch
+        }
     }
\ No newline at end of file
@@ -0,0 +1,96 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::events::EpochReportEvent;
+use crate::maintenance::MaintenancePhase;
+use crate::roles::Role;
+
+#[elrond_wasm::module]
+pub trait OracleModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    + crate::helpers::HelpersModule
+    + crate::keeper::KeeperModule
+    + crate::rate_history::RateHistoryModule
+    + crate::maintenance::MaintenanceModule
+    + crate::scoring::ScoringModule
+    {
+
+    // Epoch report
+    // - replaces getRewardsAdmin and getStakeAdmin: one (validator, stake, rewards) entry
+    //   per validator, in the order of getValidators
+    // - has to be sent before any fetch of the epoch. the rate is updated right away,
+    //   redelegation, daily delegation and withdrawals run as usual after it
+
+    #[endpoint(submitEpochReport)]
+    fn submit_epoch_report(&self, reports: MultiValueEncoded<MultiValue3<ManagedAddress, BigUint, BigUint>>) {
+        self.require_role(Role::Reporter);
+
+        let mut state = self.get_maintenance_state();
+        let current_epoch = state.epoch;
+
+        require!(
+            state.phase == MaintenancePhase::FetchRewards
+                && state.next_index == 1
+                && state.pending_callbacks == 0,
+            "Maintenance already started this epoch"
+        );
+
+        self.maintenance_phase_started_event(current_epoch, MaintenancePhase::FetchRewards);
+
+        let validators_count = self.validators().len();
+        let mut total_stake = BigUint::zero();
+        let mut total_rewards = BigUint::zero();
+        let mut index = 1usize;
+
+        for report in reports {
+            let (validator, stake, rewards) = report.into_tuple();
+
+            require!(
+                index <= validators_count && validator == self.validators().get(index),
+                "Unknown or out of order validator in report"
+            );
+
+            // scored against the stake it had, same as the fetched rewards
+            self.record_validator_rewards(&validator, current_epoch, &rewards);
+
+            // the rewards are redelegated later this epoch
+            let validator_stake = &stake + &rewards;
+            self.validator_stake_amount().insert(validator.clone(), validator_stake.clone());
+            self.validator_stake_amount_clone().insert(validator, validator_stake);
+
+            total_stake += stake;
+            total_rewards += rewards;
+            index += 1;
+        }
+
+        require!(index == validators_count + 1, "Report must cover every validator");
+
+        let active_stake = &total_stake + &total_rewards;
+        self.rewards_amounts().insert(current_epoch, total_rewards.clone());
+        self.stake_amounts().insert(current_epoch, active_stake.clone());
+        self.update_protocol_revenue(&current_epoch);
+
+        let total_pooled_egld = self.get_total_pooled_egld(active_stake);
+        let new_rate = self.compute_exchange_rate(&total_pooled_egld);
+        if let Some(rate) = &new_rate {
+            self.require_rate_change_within_bounds(rate);
+        }
+        self.set_exchange_rate(current_epoch, total_pooled_egld, new_rate);
+
+        self.last_report_epoch().set(current_epoch);
+        self.finish_maintenance_phase(&mut state);
+        self.maintenance_state().set(&state);
+
+        self.epoch_report_submitted_event(
+            current_epoch,
+            &self.blockchain().get_caller(),
+            &EpochReportEvent {
+                total_stake,
+                total_rewards,
+                exchange_rate: self.exchange_rate().get(),
+            },
+        );
+    }
+}
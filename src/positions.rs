@@ -0,0 +1,111 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::tokens::TokenAttributes;
+
+#[elrond_wasm::module]
+pub trait PositionsModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    + crate::tokens::TokenModule
+    + crate::toggles::TogglesModule
+    + elrond_wasm_modules::pause::PauseModule
+    + elrond_wasm_modules::features::FeaturesModule
+    {
+
+    // uEGLD positions
+    // - uEGLD is minted 1:1 with the EGLD owed, merging and splitting only regroup it.
+    //   the inputs are burned and new nonces are minted for the same total amount
+
+    // the merged position unlocks at the latest unlock epoch of the inputs,
+    // so positions with the same unlock epoch are merged exactly
+    #[payable("*")]
+    #[endpoint(mergePositions)]
+    fn merge_positions(&self) -> EsdtTokenPayment<Self::Api> {
+        self.require_not_paused();
+
+        let payments = self.call_value().all_esdt_transfers();
+        require!(payments.len() > 1, "Must send at least 2 positions to merge");
+
+        let undelegated_token = self.undelegated_token().get_token_id();
+        let mut total_amount = BigUint::zero();
+        let mut unstake_epoch = 0u64;
+        let mut unlock_epoch = 0u64;
+
+        for payment in payments.iter() {
+            require!(payment.token_identifier == undelegated_token, "Invalid token sent");
+            require!(payment.amount > 0, "Cannot receive 0 amount");
+
+            let attr: TokenAttributes<Self::Api> =
+                self.undelegated_token().get_token_attributes(payment.token_nonce);
+
+            if attr.unstake_epoch > unstake_epoch {
+                unstake_epoch = attr.unstake_epoch;
+            }
+            if attr.unlock_epoch > unlock_epoch {
+                unlock_epoch = attr.unlock_epoch;
+            }
+
+            self.undelegated_token().nft_burn(payment.token_nonce, &payment.amount);
+            total_amount += &payment.amount;
+        }
+
+        let caller = self.blockchain().get_caller();
+        let attr = TokenAttributes {
+            unstake_epoch,
+            unlock_epoch,
+            egld_amount: total_amount.clone(),
+        };
+
+        let merged = self.create_and_send_assets(total_amount, &caller, &attr);
+
+        self.positions_merged_event(&caller, merged.token_nonce, unlock_epoch, &merged.amount);
+
+        merged
+    }
+
+    // the new positions keep the epochs of the one split
+    #[payable("*")]
+    #[endpoint(splitPosition)]
+    fn split_position(&self, amounts: MultiValueEncoded<BigUint>) -> MultiValueEncoded<EsdtTokenPayment<Self::Api>> {
+        self.require_not_paused();
+
+        let (token, nonce, payment) = self.call_value().single_esdt().into_tuple();
+
+        require!(token == self.undelegated_token().get_token_id(), "Invalid token sent");
+        require!(payment > 0, "Cannot receive 0 amount");
+
+        let mut parts: ManagedVec<BigUint> = ManagedVec::new();
+        let mut total_amount = BigUint::zero();
+        for amount in amounts {
+            require!(amount > 0, "Cannot split off a 0 amount");
+
+            total_amount += &amount;
+            parts.push(amount);
+        }
+
+        require!(parts.len() > 1, "Must split into at least 2 positions");
+        require!(total_amount == payment, "Split amounts must add up to the position amount");
+
+        let attr: TokenAttributes<Self::Api> = self.undelegated_token().get_token_attributes(nonce);
+        let caller = self.blockchain().get_caller();
+
+        self.undelegated_token().nft_burn(nonce, &payment);
+
+        let mut result = MultiValueEncoded::new();
+        for amount in parts.iter() {
+            let part_attr = TokenAttributes {
+                unstake_epoch: attr.unstake_epoch,
+                unlock_epoch: attr.unlock_epoch,
+                egld_amount: (*amount).clone(),
+            };
+
+            result.push(self.create_and_send_assets((*amount).clone(), &caller, &part_attr));
+        }
+
+        self.position_split_event(&caller, nonce, parts.len(), &payment);
+
+        result
+    }
+}
@@ -0,0 +1,166 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+// the most entries returned by a single history query
+pub const MAX_RATE_HISTORY_PAGE: usize = 100;
+
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct RateHistoryEntry<M: ManagedTypeApi> {
+    pub epoch: u64,
+    pub rate: BigUint<M>,
+}
+
+// consumers compare last_update_epoch with the current epoch to detect a stale rate
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct RateQuote<M: ManagedTypeApi> {
+    pub rate: BigUint<M>,
+    pub last_update_epoch: u64,
+    pub current_epoch: u64,
+}
+
+#[elrond_wasm::module]
+pub trait RateHistoryModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    {
+
+    // Storage
+
+    // one entry per updateExchangeRate, ordered by epoch
+    #[storage_mapper("exchange_rate_history")]
+    fn exchange_rate_history(&self) -> VecMapper<RateHistoryEntry<Self::Api>>;
+
+    // Views
+
+    #[view(getLatestRate)]
+    fn get_latest_rate(&self) -> RateQuote<Self::Api> {
+        self.build_rate_quote(self.exchange_rate().get())
+    }
+
+    // the rate that was in effect during the epoch
+    #[view(getRateAtEpoch)]
+    fn get_rate_at_epoch(&self, epoch: u64) -> RateQuote<Self::Api> {
+        let index = match self.find_rate_index(epoch) {
+            Some(index) => index,
+            None => sc_panic!("No exchange rate recorded at this epoch"),
+        };
+
+        self.build_rate_quote(self.exchange_rate_history().get(index).rate)
+    }
+
+    // updates between the two epochs (both included), at most `limit` of them.
+    // the next page starts at the epoch after the last returned one
+    #[view(getRateHistory)]
+    fn get_rate_history(
+        &self,
+        from_epoch: u64,
+        to_epoch: u64,
+        limit: usize,
+    ) -> MultiValueEncoded<RateHistoryEntry<Self::Api>> {
+        let mut result = MultiValueEncoded::new();
+        let limit = if limit < MAX_RATE_HISTORY_PAGE { limit } else { MAX_RATE_HISTORY_PAGE };
+
+        let start_index = match self.find_rate_index(from_epoch) {
+            Some(index) if self.exchange_rate_history().get(index).epoch == from_epoch => index,
+            Some(index) => index + 1,
+            None => 1,
+        };
+
+        let mut count = 0usize;
+        for index in start_index..=self.exchange_rate_history().len() {
+            let entry = self.exchange_rate_history().get(index);
+
+            if entry.epoch > to_epoch || count == limit {
+                break;
+            }
+
+            result.push(entry);
+            count += 1;
+        }
+
+        result
+    }
+
+    // average of the rate over the last `epochs` epochs (the current one included),
+    // each rate weighted by the number of epochs it was in effect.
+    // epochs before the first update are left out
+    #[view(getTwapRate)]
+    fn get_twap_rate(&self, epochs: u64) -> RateQuote<Self::Api> {
+        require!(epochs > 0, "TWAP window must be at least 1 epoch");
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        let window_start = if current_epoch + 1 > epochs { current_epoch + 1 - epochs } else { 0 };
+
+        let mut weighted_sum = BigUint::zero();
+        let mut weighted_epochs = 0u64;
+        // the epoch right after the period of the entry being processed
+        let mut period_end = current_epoch + 1;
+
+        let mut index = self.exchange_rate_history().len();
+        while index > 0 && period_end > window_start {
+            let entry = self.exchange_rate_history().get(index);
+            let period_start = if entry.epoch > window_start { entry.epoch } else { window_start };
+
+            if period_end > period_start {
+                let period = period_end - period_start;
+                weighted_sum += entry.rate * BigUint::from(period);
+                weighted_epochs += period;
+            }
+
+            period_end = period_start;
+            index -= 1;
+        }
+
+        require!(weighted_epochs > 0, "No exchange rate recorded in this window");
+
+        self.build_rate_quote(weighted_sum / weighted_epochs)
+    }
+
+    // Helpers
+
+    // called by updateExchangeRate, a second update in the same epoch replaces the entry
+    fn record_exchange_rate(&self, epoch: u64, rate: &BigUint) {
+        let entry = RateHistoryEntry {
+            epoch,
+            rate: rate.clone(),
+        };
+
+        let len = self.exchange_rate_history().len();
+        if len > 0 && self.exchange_rate_history().get(len).epoch == epoch {
+            self.exchange_rate_history().set(len, &entry);
+        } else {
+            self.exchange_rate_history().push(&entry);
+        }
+
+        self.last_rate_update_epoch().set(epoch);
+    }
+
+    // index of the last update made at or before the epoch
+    fn find_rate_index(&self, epoch: u64) -> Option<usize> {
+        let mut low = 1usize;
+        let mut high = self.exchange_rate_history().len();
+        let mut found = None;
+
+        while low <= high {
+            let middle = low + (high - low) / 2;
+
+            if self.exchange_rate_history().get(middle).epoch <= epoch {
+                found = Some(middle);
+                low = middle + 1;
+            } else {
+                high = middle - 1;
+            }
+        }
+
+        found
+    }
+
+    fn build_rate_quote(&self, rate: BigUint) -> RateQuote<Self::Api> {
+        RateQuote {
+            rate,
+            last_update_epoch: self.last_rate_update_epoch().get(),
+            current_epoch: self.blockchain().get_block_epoch(),
+        }
+    }
+}
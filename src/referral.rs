@@ -0,0 +1,154 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::helpers::{MAX_BPS, REFERRAL_PRECISION};
+use crate::roles::Role;
+
+// epochs a referrer earns on the stake of a user it referred.
+// stEGLD can be transferred away without unstaking, so the share can't last forever
+pub const REFERRAL_PERIOD: u64 = 180;
+
+#[elrond_wasm::module]
+pub trait ReferralModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    + crate::helpers::HelpersModule
+    {
+
+    #[endpoint(setReferralShare)]
+    fn set_referral_share(&self, share: u64) {
+        self.require_role(Role::FeeManager);
+
+        require!(share <= MAX_BPS, "Referral share cannot exceed 100%");
+        self.referral_share().set(share);
+    }
+
+    #[endpoint(claimReferralRewards)]
+    fn claim_referral_rewards(&self) {
+        let caller = self.blockchain().get_caller();
+
+        self.settle_referrer(&caller);
+
+        let rewards = self.referral_rewards(&caller).get();
+        require!(rewards > 0, "No referral rewards to claim");
+
+        let st_egld_id = self.staked_egld_id().get();
+        let st_egld_amount = self.mint_fees(&rewards);
+
+        self.referral_rewards(&caller).clear();
+        self.send().direct_esdt(&caller, &st_egld_id, 0, &st_egld_amount);
+
+        self.referral_rewards_claimed_event(&caller, &rewards, &st_egld_amount);
+    }
+
+    // anyone can stop an expired referral from earning
+    #[endpoint(expireReferral)]
+    fn expire_referral(&self, user: ManagedAddress) {
+        require!(!self.referrer(&user).is_empty(), "Address was not referred");
+        require!(self.is_referral_expired(&user), "Referral has not expired yet");
+        require!(self.referred_deposits(&user).get() > 0, "No referred stake left");
+
+        self.remove_expired_referral(&user);
+    }
+
+    // settled and pending rewards, in EGLD
+    #[view(getReferralRewards)]
+    fn get_referral_rewards(&self, referrer: ManagedAddress) -> BigUint {
+        self.referral_rewards(&referrer).get() + self.get_pending_referral_rewards(&referrer)
+    }
+
+    // Helpers
+
+    // the first referrer of a user is kept for all of its stakes, until the referral expires.
+    // a second address of the same owner can't be told apart on-chain, so self-referral
+    // is allowed: it only gives back the referral share of one's own fee, for REFERRAL_PERIOD
+    fn record_referrer(&self, user: &ManagedAddress, referrer: &ManagedAddress) {
+        require!(user != referrer, "Cannot refer yourself");
+
+        if self.referrer(user).is_empty() {
+            let current_epoch = self.blockchain().get_block_epoch();
+
+            self.referrer(user).set(referrer);
+            self.referral_expiry_epoch(user).set(current_epoch + REFERRAL_PERIOD);
+            self.referral_recorded_event(user, referrer);
+        }
+    }
+
+    fn add_referred_deposit(&self, user: &ManagedAddress, amount: &BigUint) {
+        if self.referrer(user).is_empty() {
+            return;
+        }
+
+        // the referrer is kept, so an expired referral can't be recorded again
+        if self.is_referral_expired(user) {
+            self.remove_expired_referral(user);
+            return;
+        }
+
+        let referrer = self.referrer(user).get();
+        self.settle_referrer(&referrer);
+
+        self.referred_deposits(user).update(|deposits| *deposits += amount);
+        self.referred_stake(&referrer).update(|stake| *stake += amount);
+        self.total_referred_stake().update(|stake| *stake += amount);
+    }
+
+    // unstaked EGLD stops counting for the referrer, up to what the user deposited
+    fn remove_referred_deposit(&self, user: &ManagedAddress, amount: &BigUint) {
+        let deposits = self.referred_deposits(user).get();
+        if self.referrer(user).is_empty() || deposits == 0 {
+            return;
+        }
+
+        let referrer = self.referrer(user).get();
+        self.settle_referrer(&referrer);
+
+        let removed = if &deposits < amount {
+            deposits.clone()
+        } else {
+            amount.clone()
+        };
+
+        self.referred_deposits(user).set(&deposits - &removed);
+        self.referred_stake(&referrer).update(|stake| *stake -= &removed);
+        self.total_referred_stake().update(|stake| *stake -= &removed);
+    }
+
+    #[inline]
+    fn is_referral_expired(&self, user: &ManagedAddress) -> bool {
+        self.blockchain().get_block_epoch() >= self.referral_expiry_epoch(user).get()
+    }
+
+    fn remove_expired_referral(&self, user: &ManagedAddress) {
+        let deposits = self.referred_deposits(user).get();
+        if deposits == 0 {
+            return;
+        }
+
+        let referrer = self.referrer(user).get();
+        self.settle_referrer(&referrer);
+
+        self.referred_deposits(user).clear();
+        self.referred_stake(&referrer).update(|stake| *stake -= &deposits);
+        self.total_referred_stake().update(|stake| *stake -= &deposits);
+
+        self.referral_expired_event(user, &referrer, &deposits);
+    }
+
+    fn settle_referrer(&self, referrer: &ManagedAddress) {
+        let pending = self.get_pending_referral_rewards(referrer);
+        if pending > 0 {
+            self.referral_rewards(referrer).update(|rewards| *rewards += pending);
+        }
+
+        self.referrer_revenue_index(referrer).set(self.referral_revenue_per_stake().get());
+    }
+
+    fn get_pending_referral_rewards(&self, referrer: &ManagedAddress) -> BigUint {
+        let revenue_per_stake = self.referral_revenue_per_stake().get();
+        let referrer_index = self.referrer_revenue_index(referrer).get();
+
+        self.referred_stake(referrer).get() * (revenue_per_stake - referrer_index) / REFERRAL_PRECISION
+    }
+}
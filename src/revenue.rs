@@ -0,0 +1,121 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::events::RevenuePayoutEvent;
+use crate::helpers::MAX_BPS;
+use crate::roles::Role;
+
+// a treasury receiving part of the protocol revenue (DAO treasury, team, insurance fund, ...)
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct RevenueRecipient<M: ManagedTypeApi> {
+    pub address: ManagedAddress<M>,
+    pub name: ManagedBuffer<M>,
+    pub share: u64,
+}
+
+#[elrond_wasm::module]
+pub trait RevenueModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    + crate::helpers::HelpersModule
+    {
+
+    // Storage
+
+    // shares are in basis points and sum up to 100%.
+    // the owner gets everything while the list is empty
+    #[view(getRevenueRecipients)]
+    #[storage_mapper("revenue_recipients")]
+    fn revenue_recipients(&self) -> VecMapper<RevenueRecipient<Self::Api>>;
+
+    // replaces the whole list, as (address, name, share) entries
+    // not timelocked: it only splits the protocol's own share, user funds are not affected
+    #[endpoint(setRevenueRecipients)]
+    fn set_revenue_recipients(
+        &self,
+        recipients: MultiValueEncoded<MultiValue3<ManagedAddress, ManagedBuffer, u64>>,
+    ) {
+        self.require_role(Role::FeeManager);
+
+        let mut total_share = 0u64;
+        self.revenue_recipients().clear();
+
+        for recipient in recipients {
+            let (address, name, share) = recipient.into_tuple();
+
+            require!(share > 0, "Recipient share must be bigger than 0");
+            total_share += share;
+
+            self.revenue_recipients().push(&RevenueRecipient {
+                address,
+                name,
+                share,
+            });
+        }
+
+        require!(total_share == MAX_BPS, "Recipient shares must sum up to 100%");
+    }
+
+    // the endpoint for distributing the protocol fees
+    // protocol rewards are in stEGLD.
+    // this endpoint mints the revenue at the current rate and sends each recipient its share
+    #[endpoint(distributeProtocolRevenue)]
+    fn distribute_protocol_revenue(&self) {
+        let st_egld_id = self.staked_egld_id().get();
+        let protocol_revenue = self.protocol_revenue().get();
+
+        require!(protocol_revenue > 0, "No protocol revenue to distribute");
+
+        let amount_to_send = self.mint_fees(&protocol_revenue);
+        self.protocol_revenue().set(BigUint::from(0u64));
+
+        let mut recipients = ManagedVec::<Self::Api, RevenueRecipient<Self::Api>>::new();
+        for recipient in self.revenue_recipients().iter() {
+            recipients.push(recipient);
+        }
+
+        if recipients.is_empty() {
+            recipients.push(RevenueRecipient {
+                address: self.blockchain().get_owner_address(),
+                name: ManagedBuffer::from(&b"owner"[..]),
+                share: MAX_BPS,
+            });
+        }
+
+        // the last recipient also gets the rounding leftovers
+        let current_epoch = self.blockchain().get_block_epoch();
+        let last_index = recipients.len() - 1;
+        let mut egld_left = protocol_revenue.clone();
+        let mut st_egld_left = amount_to_send.clone();
+
+        for (index, recipient) in recipients.iter().enumerate() {
+            let (egld_amount, st_egld_amount) = if index == last_index {
+                (egld_left.clone(), st_egld_left.clone())
+            } else {
+                (
+                    &protocol_revenue * &BigUint::from(recipient.share) / MAX_BPS,
+                    &amount_to_send * &BigUint::from(recipient.share) / MAX_BPS,
+                )
+            };
+
+            egld_left -= &egld_amount;
+            st_egld_left -= &st_egld_amount;
+
+            if st_egld_amount > 0 {
+                self.send().direct_esdt(&recipient.address, &st_egld_id, 0, &st_egld_amount);
+            }
+
+            self.revenue_payout_event(
+                &recipient.address,
+                current_epoch,
+                &RevenuePayoutEvent {
+                    name: recipient.name,
+                    share: recipient.share,
+                    egld_amount,
+                    st_egld_amount,
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,77 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+// the owner implicitly holds every role
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, Copy, PartialEq)]
+pub enum Role {
+    // protocol settings, accounting corrections, token setup and role management
+    Admin,
+    // daily maintenance, direct delegations and validator exits
+    Operator,
+    // service fee, instant unstake fee and keeper bounty
+    FeeManager,
+    // pausing and resuming the contract
+    Pauser,
+    // validator registry and scoring settings
+    ValidatorManager,
+    // submitting the epoch reports of the off-chain oracle
+    Reporter,
+}
+
+#[elrond_wasm::module]
+pub trait RolesModule:
+    crate::events::EventsModule
+    {
+
+    #[view(getRoleMembers)]
+    #[storage_mapper("role_members")]
+    fn role_members(&self, role: Role) -> UnorderedSetMapper<ManagedAddress>;
+
+    // admins can grant every role except admin, which only the owner grants
+    #[endpoint(grantRole)]
+    fn grant_role(&self, role: Role, address: ManagedAddress) {
+        let caller = self.require_role_manager(role);
+
+        require!(self.role_members(role).insert(address.clone()), "Address already has this role");
+
+        self.role_granted_event(role, &address, &caller);
+    }
+
+    #[endpoint(revokeRole)]
+    fn revoke_role(&self, role: Role, address: ManagedAddress) {
+        let caller = self.require_role_manager(role);
+
+        require!(self.role_members(role).swap_remove(&address), "Address does not have this role");
+
+        self.role_revoked_event(role, &address, &caller);
+    }
+
+    #[view(hasRole)]
+    fn has_role(&self, role: Role, address: ManagedAddress) -> bool {
+        address == self.blockchain().get_owner_address() || self.role_members(role).contains(&address)
+    }
+
+    // Helpers
+
+    fn require_role(&self, role: Role) {
+        let caller = self.blockchain().get_caller();
+
+        require!(self.has_role(role, caller), "Caller does not have the required role");
+    }
+
+    fn require_role_manager(&self, role: Role) -> ManagedAddress {
+        let caller = self.blockchain().get_caller();
+        let is_owner = caller == self.blockchain().get_owner_address();
+
+        if role == Role::Admin {
+            require!(is_owner, "Only the owner can manage admins");
+        } else {
+            require!(
+                is_owner || self.role_members(Role::Admin).contains(&caller),
+                "Caller does not have the required role"
+            );
+        }
+
+        caller
+    }
+}
@@ -0,0 +1,183 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::helpers::MAX_BPS;
+use crate::roles::Role;
+
+// yield per staked EGLD is scaled by this value
+pub const SCORE_PRECISION: u64 = 1_000_000_000_000_000_000;
+pub const DEFAULT_SCORING_WINDOW: u64 = 30;
+
+// each rewards callback reads the whole window, it has to fit in the callback gas
+pub const MAX_SCORING_WINDOW: u64 = 90;
+
+// a validator can get at most twice its weight from its score
+const MAX_SCORE_RATIO: u64 = 2 * MAX_BPS;
+
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct ValidatorPerformance<M: ManagedTypeApi> {
+    pub stake: BigUint<M>,
+    pub rewards: BigUint<M>,
+}
+
+#[elrond_wasm::module]
+pub trait ScoringModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    {
+
+    // Storage
+
+    #[view(getValidatorPerformance)]
+    #[storage_mapper("validator_performance")]
+    fn validator_performance(&self, validator: &ManagedAddress) -> MapMapper<u64, ValidatorPerformance<Self::Api>>;
+
+    // realised yield per staked EGLD over the scoring window
+    #[view(getValidatorScore)]
+    #[storage_mapper("validator_score")]
+    fn validator_score(&self, validator: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[view(getAverageValidatorScore)]
+    #[storage_mapper("average_validator_score")]
+    fn average_validator_score(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getScoringWindow)]
+    #[storage_mapper("scoring_window")]
+    fn scoring_window(&self) -> SingleValueMapper<u64>;
+
+    // basis points of the allocation weight driven by the score.
+    // 0 ignores the scores, 10000 makes the weight proportional to the score
+    #[view(getScoreInfluence)]
+    #[storage_mapper("score_influence")]
+    fn score_influence(&self) -> SingleValueMapper<u64>;
+
+    #[endpoint(setScoringWindow)]
+    fn set_scoring_window(&self, epochs: u64) {
+        self.require_role(Role::ValidatorManager);
+
+        require!(epochs > 0, "Scoring window must be at least 1 epoch");
+        require!(epochs <= MAX_SCORING_WINDOW, "Scoring window is too long");
+        self.scoring_window().set(epochs);
+    }
+
+    #[endpoint(setScoreInfluence)]
+    fn set_score_influence(&self, influence: u64) {
+        self.require_role(Role::ValidatorManager);
+
+        require!(influence <= MAX_BPS, "Score influence cannot exceed 100%");
+        self.score_influence().set(influence);
+    }
+
+    // Scoring
+
+    // records the rewards of a validator for the epoch against the stake it had,
+    // then recomputes its score and the average score
+    fn record_validator_rewards(&self, validator: &ManagedAddress, epoch: u64, rewards: &BigUint) {
+        let stake = match self.validator_stake_amount().get(validator) {
+            Some(n) => n,
+            None => BigUint::zero(),
+        };
+
+        // nothing to score without stake, new validators stay neutral
+        if stake == 0 {
+            return;
+        }
+
+        self.validator_performance(validator).insert(
+            epoch,
+            ValidatorPerformance {
+                stake,
+                rewards: rewards.clone(),
+            },
+        );
+
+        // every entry out of the window is dropped, epochs can be skipped and the window can shrink
+        let first_epoch = self.get_scoring_first_epoch(epoch);
+        let mut expired_epochs: ManagedVec<u64> = ManagedVec::new();
+        for performance_epoch in self.validator_performance(validator).keys() {
+            if performance_epoch < first_epoch {
+                expired_epochs.push(performance_epoch);
+            }
+        }
+        for expired_epoch in expired_epochs.iter() {
+            self.validator_performance(validator).remove(&expired_epoch);
+        }
+
+        self.validator_score(validator).set(&self.compute_validator_score(validator, epoch));
+        self.update_average_validator_score();
+    }
+
+    fn compute_validator_score(&self, validator: &ManagedAddress, epoch: u64) -> BigUint {
+        let first_epoch = self.get_scoring_first_epoch(epoch);
+
+        let mut total_stake = BigUint::zero();
+        let mut total_rewards = BigUint::zero();
+        for (performance_epoch, performance) in self.validator_performance(validator).iter() {
+            if performance_epoch >= first_epoch && performance_epoch <= epoch {
+                total_stake += performance.stake;
+                total_rewards += performance.rewards;
+            }
+        }
+
+        if total_stake == 0 {
+            return BigUint::zero();
+        }
+
+        total_rewards * BigUint::from(SCORE_PRECISION) / total_stake
+    }
+
+    fn update_average_validator_score(&self) {
+        let mut total_score = BigUint::zero();
+        let mut scored_validators = 0u64;
+
+        for validator in self.validators().iter() {
+            let score = self.validator_score(&validator).get();
+            if score > 0 {
+                total_score += score;
+                scored_validators += 1;
+            }
+        }
+
+        if scored_validators > 0 {
+            self.average_validator_score().set(total_score / scored_validators);
+        }
+    }
+
+    // multiplier applied to the target weight, in basis points.
+    // validators without a score yet are treated as average
+    fn get_score_factor(&self, validator: &ManagedAddress) -> u64 {
+        let influence = self.score_influence().get();
+        let score = self.validator_score(validator).get();
+        let average_score = self.average_validator_score().get();
+
+        let has_history = !self.validator_performance(validator).is_empty();
+
+        let ratio = if !has_history || average_score == 0 {
+            MAX_BPS
+        } else {
+            match (score * BigUint::from(MAX_BPS) / average_score).to_u64() {
+                Some(ratio) if ratio < MAX_SCORE_RATIO => ratio,
+                _ => MAX_SCORE_RATIO,
+            }
+        };
+
+        (MAX_BPS - influence) + influence * ratio / MAX_BPS
+    }
+
+    // first epoch of the window ending at `epoch`
+    fn get_scoring_first_epoch(&self, epoch: u64) -> u64 {
+        let window = self.get_scoring_window();
+
+        if epoch >= window { epoch - window + 1 } else { 0 }
+    }
+
+    #[inline]
+    fn get_scoring_window(&self) -> u64 {
+        if self.scoring_window().is_empty() {
+            DEFAULT_SCORING_WINDOW
+        } else {
+            self.scoring_window().get()
+        }
+    }
+}
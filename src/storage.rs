@@ -1,6 +1,13 @@
 elrond_wasm::imports!();
 elrond_wasm::derive_imports!();
 
+use crate::maintenance::{MaintenancePhase, MaintenanceState};
+use crate::roles::Role;
+use crate::validators::{ValidatorExit, ValidatorInfo};
+
+// the delegation contracts unbond in 10 epochs, this leaves room for a late withdrawal
+pub const MAX_UNBOND_PERIOD: u64 = 30;
+
 #[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode,TypeAbi, Clone)]
 pub struct StakeAmount<M: ManagedTypeApi> {
     pub epoch: u64,
@@ -13,8 +20,17 @@ pub struct RewardsAmount<M: ManagedTypeApi> {
     pub amount: BigUint<M>
 }
 
+#[derive(ManagedVecItem, TopEncode, TopDecode, NestedEncode, NestedDecode,TypeAbi, Clone)]
+pub struct DelegationPlanItem<M: ManagedTypeApi> {
+    pub validator: ManagedAddress<M>,
+    pub amount: BigUint<M>
+}
+
 #[elrond_wasm::module]
-pub trait StorageModule {
+pub trait StorageModule:
+    crate::roles::RolesModule
+    + crate::events::EventsModule
+    {
 
     // Validators
 
@@ -31,6 +47,13 @@ pub trait StorageModule {
     #[storage_mapper("validator_stake_amount")]
     fn validator_stake_amount(&self) -> MapMapper<ManagedAddress,BigUint>;
 
+    #[storage_mapper("validator_info")]
+    fn validator_info(&self, validator: &ManagedAddress) -> SingleValueMapper<ValidatorInfo<Self::Api>>;
+
+    #[view(getValidatorExit)]
+    #[storage_mapper("validator_exit")]
+    fn validator_exit(&self, validator: &ManagedAddress) -> SingleValueMapper<ValidatorExit<Self::Api>>;
+
     // this "clone" is used for removing full validators, 
     // so the delegation process can still take place
 
@@ -54,6 +77,12 @@ pub trait StorageModule {
     #[storage_mapper("total_token_supply")]
     fn total_token_supply(&self) -> SingleValueMapper<BigUint>;
 
+    // active stake + buffer + pending undelegations - liabilities to uEGLD holders.
+    // the exchange rate is derived from it
+    #[view(getTotalPooledEgld)]
+    #[storage_mapper("total_pooled_egld")]
+    fn total_pooled_egld(&self) -> SingleValueMapper<BigUint>;
+
     #[view(getExchangeRate)]
     #[storage_mapper("exchange_rate")]
     fn exchange_rate(&self) -> SingleValueMapper<BigUint>;
@@ -74,38 +103,141 @@ pub trait StorageModule {
     #[storage_mapper("rewards_amount")]
     fn rewards_amount(&self) -> VecMapper<RewardsAmount<Self::Api>>;
 
+    // EGLD value of the fees not distributed yet
     #[view(getProtocolRevenue)]
     #[storage_mapper("protocol_revenue")]
     fn protocol_revenue(&self) -> SingleValueMapper<BigUint>;
 
-    #[view(getServiceFee)]
-    #[storage_mapper("service_fee")]
-    fn service_fee(&self) -> SingleValueMapper<BigUint>;
+    // fees accrued in each epoch, including the referrers' share
+    #[view(getProtocolRevenueHistory)]
+    #[storage_mapper("protocol_revenue_history")]
+    fn protocol_revenue_history(&self) -> MapMapper<u64, BigUint>;
 
-    // Mappers
-    // - used for the maintenance tasks
+    #[view(getTotalProtocolRevenue)]
+    #[storage_mapper("total_protocol_revenue")]
+    fn total_protocol_revenue(&self) -> SingleValueMapper<BigUint>;
 
+    // EGLD value of the fees owed to revenue recipients, keepers and referrers that was not
+    // minted as stEGLD yet. it is part of the pooled EGLD but doesn't back the stEGLD supply
+    #[view(getUnmintedFees)]
+    #[storage_mapper("unminted_fees")]
+    fn unminted_fees(&self) -> SingleValueMapper<BigUint>;
 
-    #[view(getMappingIndex)]
-    #[storage_mapper("mapping_index")]
-    fn mapping_index(&self) -> SingleValueMapper<usize>;
+    #[view(getLastFeeAccrualEpoch)]
+    #[storage_mapper("last_fee_accrual_epoch")]
+    fn last_fee_accrual_epoch(&self) -> SingleValueMapper<u64>;
 
-    #[view(getRewardsMappingIndex)]
-    #[storage_mapper("rewards_mapping_index")]
-    fn rewards_mapping_index(&self) -> SingleValueMapper<usize>;
+    #[view(getLastRateUpdateEpoch)]
+    #[storage_mapper("last_rate_update_epoch")]
+    fn last_rate_update_epoch(&self) -> SingleValueMapper<u64>;
 
-    #[view(getWithdrawMappingIndex)]
-    #[storage_mapper("withdraw_mapping_index")]
-    fn withdraw_mapping_index(&self) -> SingleValueMapper<usize>;
+    #[view(getServiceFee)]
+    #[storage_mapper("service_fee")]
+    fn service_fee(&self) -> SingleValueMapper<BigUint>;
+
+    // Liquidity buffer
+
+    // EGLD deposits kept in the contract, not delegated yet
+    #[view(getLiquidityBuffer)]
+    #[storage_mapper("liquidity_buffer")]
+    fn liquidity_buffer(&self) -> SingleValueMapper<BigUint>;
+
+    // basis points of the TVL kept in the buffer by the daily delegation
+    #[view(getBufferTarget)]
+    #[storage_mapper("buffer_target")]
+    fn buffer_target(&self) -> SingleValueMapper<u64>;
+
+    // basis points charged on instant unstakes
+    #[view(getInstantUnstakeFee)]
+    #[storage_mapper("instant_unstake_fee")]
+    fn instant_unstake_fee(&self) -> SingleValueMapper<u64>;
+
+    // Unbonding
+
+    // number of epochs a uEGLD position has to wait before it can be claimed
+    #[view(getUnbondPeriod)]
+    #[storage_mapper("unbond_period")]
+    fn unbond_period(&self) -> SingleValueMapper<u64>;
+
+    // EGLD owed to uEGLD holders that was not claimed yet
+    #[view(getTotalUnbonding)]
+    #[storage_mapper("total_unbonding")]
+    fn total_unbonding(&self) -> SingleValueMapper<BigUint>;
+
+    // EGLD undelegated from validators that was not withdrawn yet
+    #[view(getPendingUndelegation)]
+    #[storage_mapper("pending_undelegation")]
+    fn pending_undelegation(&self) -> SingleValueMapper<BigUint>;
+
+    // EGLD withdrawn from validators and reserved for uEGLD claims
+    #[view(getWithdrawnEgld)]
+    #[storage_mapper("withdrawn_egld")]
+    fn withdrawn_egld(&self) -> SingleValueMapper<BigUint>;
+
+    // Referrals
+    // - referrers get a share of the protocol fee earned on the stake they brought,
+    //   see ReferralModule
+
+    // basis points of the protocol fee paid to referrers
+    #[view(getReferralShare)]
+    #[storage_mapper("referral_share")]
+    fn referral_share(&self) -> SingleValueMapper<u64>;
+
+    #[view(getReferrer)]
+    #[storage_mapper("referrer")]
+    fn referrer(&self, user: &ManagedAddress) -> SingleValueMapper<ManagedAddress>;
+
+    // the stake of a referred user stops counting for its referrer after this epoch
+    #[view(getReferralExpiryEpoch)]
+    #[storage_mapper("referral_expiry_epoch")]
+    fn referral_expiry_epoch(&self, user: &ManagedAddress) -> SingleValueMapper<u64>;
+
+    // EGLD staked by a referred user that still counts for its referrer
+    #[view(getReferredDeposits)]
+    #[storage_mapper("referred_deposits")]
+    fn referred_deposits(&self, user: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[view(getReferredStake)]
+    #[storage_mapper("referred_stake")]
+    fn referred_stake(&self, referrer: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[view(getTotalReferredStake)]
+    #[storage_mapper("total_referred_stake")]
+    fn total_referred_stake(&self) -> SingleValueMapper<BigUint>;
+
+    // referral revenue accrued per referred EGLD since deployment, scaled by REFERRAL_PRECISION
+    #[view(getReferralRevenuePerStake)]
+    #[storage_mapper("referral_revenue_per_stake")]
+    fn referral_revenue_per_stake(&self) -> SingleValueMapper<BigUint>;
+
+    // value of referral_revenue_per_stake when the referrer was last settled
+    #[storage_mapper("referrer_revenue_index")]
+    fn referrer_revenue_index(&self, referrer: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    // settled referral rewards, in EGLD
+    #[storage_mapper("referral_rewards")]
+    fn referral_rewards(&self, referrer: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    // Maintenance state
+    // - one state per epoch, see MaintenanceModule
+
+    #[storage_mapper("maintenance_state")]
+    fn maintenance_state(&self) -> SingleValueMapper<MaintenanceState>;
+
+    // bumped by resetMaintenancePhase, callbacks of the calls sent before a reset are stale
+    #[view(getMaintenanceResets)]
+    #[storage_mapper("maintenance_resets")]
+    fn maintenance_resets(&self) -> SingleValueMapper<u64>;
+
+    // last epoch covered by a submitEpochReport, its stake fetch and rate update are skipped
+    #[view(getLastReportEpoch)]
+    #[storage_mapper("last_report_epoch")]
+    fn last_report_epoch(&self) -> SingleValueMapper<u64>;
 
     #[view(getStakeValue)]
     #[storage_mapper("stake_value")]
     fn stake_value(&self) -> SingleValueMapper<BigUint>;
 
-    #[view(getRedelegateMappingIndex)]
-    #[storage_mapper("redelegate_mapping_index")]
-    fn redelegate_mapping_index(&self) -> SingleValueMapper<usize>;
-
     // Maintenance info
 
     #[view(getStakeAmounts)]
@@ -116,49 +248,17 @@ pub trait StorageModule {
     #[storage_mapper("rewards_amounts")]
     fn rewards_amounts(&self) -> MapMapper<u64,BigUint>;
 
+    // Delegation plan
+    // - computed by the first dailyDelegation call of the epoch,
+    //   one item is sent per call (the maintenance state holds the index)
 
-    #[view(getStakeInfoFinished)]
-    #[storage_mapper("stake_info_finished")]
-    fn stake_info_finished(&self) -> SetMapper<u64>;
-
-    #[view(getRewardsInfoFinished)]
-    #[storage_mapper("rewards_info_finished")]
-    fn rewards_info_finished(&self) -> SetMapper<u64>;
-
-    #[view(getWithdrawFinished)]
-    #[storage_mapper("withdraw_finished")]
-    fn withdraw_finished(&self) -> SetMapper<u64>;
-
-    #[view(getRedelegateFinished)]
-    #[storage_mapper("redelegate_finished")]
-    fn redelegate_finished(&self) -> SetMapper<u64>;
-
-    #[view(getExchangeRateUpdateFinished)]
-    #[storage_mapper("exchange_rate_update_finished")]
-    fn exchange_rate_update_finished(&self) -> SetMapper<u64>;
-
-    #[view(getDailyDelegationFinished)]
-    #[storage_mapper("daily_delegation_finished")]
-    fn daily_delegation_finished(&self) -> SetMapper<u64>;
-
-
-    #[view(getStakeInfoStarted)]
-    #[storage_mapper("stake_info_started")]
-    fn stake_info_started(&self) -> SetMapper<u64>;
+    #[view(getDelegationPlan)]
+    #[storage_mapper("delegation_plan")]
+    fn delegation_plan(&self) -> VecMapper<DelegationPlanItem<Self::Api>>;
 
-    #[view(getRewardsInfoStarted)]
-    #[storage_mapper("rewards_info_started")]
-    fn rewards_info_started(&self) -> SetMapper<u64>;
-
-    #[view(getWithdrawStarted)]
-    #[storage_mapper("withdraw_started")]
-    fn withdraw_started(&self) -> SetMapper<u64>;
-
-    #[view(getRedelegateStarted)]
-    #[storage_mapper("redelegate_started")]
-    fn redelegate_started(&self) -> SetMapper<u64>;
-    
-    
+    #[view(isUndelegationPlan)]
+    #[storage_mapper("delegation_plan_is_undelegation")]
+    fn delegation_plan_is_undelegation(&self) -> SingleValueMapper<bool>;
 
     // Tokens
 
@@ -174,78 +274,64 @@ pub trait StorageModule {
         Storage modifiers
     */
 
-    #[only_owner]
-    #[endpoint(setValidatorStakeAmount)]
-    fn set_validator_stake_amount(&self, validator: ManagedAddress, amount: BigUint) {
-        self.validator_stake_amount().insert(validator, amount);
-    }
-
-    #[only_owner]
-    #[endpoint(setServiceFee)]
-    fn set_service_fee(&self, amount: BigUint) {
-        self.service_fee().set(amount);
-    }
-
-    #[only_owner]
-    #[endpoint(setMappingIndex)]
-    fn set_mapping_index(&self, index: usize) {
-        self.mapping_index().set(index);
-    }
+    #[endpoint(setBufferTarget)]
+    fn set_buffer_target(&self, target: u64) {
+        self.require_role(Role::Admin);
 
-    #[only_owner]
-    #[endpoint(setRewardsMappingIndex)]
-    fn set_rewards_mapping_index(&self, index: usize) {
-        self.rewards_mapping_index().set(index);
+        require!(target <= crate::helpers::MAX_BPS, "Buffer target cannot exceed 100%");
+        self.buffer_target().set(target);
     }
 
-    #[only_owner]
-    #[endpoint(setDeltaStake)]
-    fn set_delta_stake(&self, amount: BigInt) {
-        self.delta_stake().set(&amount);
-    }
+    #[endpoint(setUnbondPeriod)]
+    fn set_unbond_period(&self, epochs: u64) {
+        self.require_role(Role::Admin);
 
-    #[only_owner]
-    #[endpoint(setTotalStaked)]
-    fn set_total_staked(&self, amount: BigUint) {
-        self.total_staked().set(amount);
+        require!(epochs <= MAX_UNBOND_PERIOD, "Unbond period is too long");
+        self.unbond_period().set(epochs);
     }
 
-    #[only_owner]
     #[endpoint(clearRewardsAmounts)]
     fn clear_rewards_amounts(&self) {
+        self.require_role(Role::Admin);
+
         self.rewards_amounts().clear();
     }
 
+    #[endpoint(clearValidators)]
+    fn clear_validators(&self) {
+        self.require_role(Role::Admin);
+        self.require_no_maintenance_in_progress();
 
-    #[only_owner]
-    #[endpoint(clearRewardsFinished)]
-    fn clear_rewards_finished(&self) {
-        self.rewards_info_finished().clear();
+        for validator in self.validators().iter() {
+            self.validator_info(&validator).clear();
+        }
+        self.validators().clear();
     }
 
-    #[only_owner]
-    #[endpoint(clearRewardsStarted)]
-    fn clear_rewards_started(&self) {
-        self.rewards_info_started().clear();
+    // also used by governance proposals and the timelock, the fee is in thousandths
+    fn update_service_fee(&self, amount: BigUint) {
+        require!(amount <= 1000u64, "Service fee cannot exceed 100%");
+        self.service_fee().set(amount);
     }
 
-    #[only_owner]
-    #[endpoint(clearWithdrawStarted)]
-    fn clear_withdraw_started(&self) {
-        self.withdraw_started().clear();
+    // the maintenance phases and submitEpochReport go through the validators by position,
+    // the list can't be reordered while this epoch's maintenance runs
+    fn require_no_maintenance_in_progress(&self) {
+        if self.maintenance_state().is_empty() {
+            return;
+        }
+
+        let state = self.maintenance_state().get();
+        require!(
+            state.epoch != self.blockchain().get_block_epoch() || state.phase == MaintenancePhase::Completed,
+            "Maintenance is in progress this epoch"
+        );
     }
 
-
-    #[only_owner]
-    #[endpoint(clearValidatorStakeAmounts)]
-    fn clear_validator_stake_amounts(&self) {
-        self.validator_stake_amount().clear();
+    // basis points, set through the timelock
+    fn update_instant_unstake_fee(&self, fee: u64) {
+        require!(fee <= crate::helpers::MAX_BPS, "Fee cannot exceed 100%");
+        self.instant_unstake_fee().set(fee);
     }
 
-    #[only_owner]
-    #[endpoint(clearValidators)]
-    fn clear_validators(&self) {
-        self.validators().clear();
-    }
-
-}
\ No newline at end of file
+}
@@ -0,0 +1,285 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::roles::Role;
+
+pub const DEFAULT_TIMELOCK_PERIOD: u64 = 3;
+
+// a shorter period would let the next changes through right away
+pub const MIN_TIMELOCK_PERIOD: u64 = 1;
+
+// the sensitive settings, they only change once the timelock is over
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub enum TimelockAction<M: ManagedTypeApi> {
+    SetServiceFee(BigUint<M>),
+    SetDeltaStake(BigInt<M>),
+    SetTotalStaked(BigUint<M>),
+    SetValidatorStakeAmount(ManagedAddress<M>, BigUint<M>),
+    SetTimelockPeriod(u64),
+    ClearValidatorStakeAmounts,
+    SetInstantUnstakeFee(u64),
+    // address, name, target weight and cap
+    AddValidator(ManagedAddress<M>, ManagedBuffer<M>, u64, BigUint<M>),
+    RetireValidator(ManagedAddress<M>),
+    SetValidatorWeight(ManagedAddress<M>, u64),
+    SetValidatorCap(ManagedAddress<M>, BigUint<M>),
+}
+
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, Copy, PartialEq)]
+pub enum TimelockStatus {
+    Pending,
+    Executed,
+    Cancelled,
+}
+
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct TimelockedChange<M: ManagedTypeApi> {
+    pub id: usize,
+    pub action: TimelockAction<M>,
+    pub status: TimelockStatus,
+    pub queued_epoch: u64,
+    pub effective_epoch: u64,
+}
+
+#[elrond_wasm::module]
+pub trait TimelockModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    + crate::validators::ValidatorsModule
+    {
+
+    // Storage
+
+    #[storage_mapper("timelocked_changes")]
+    fn timelocked_changes(&self) -> VecMapper<TimelockedChange<Self::Api>>;
+
+    #[storage_mapper("pending_timelocked_changes")]
+    fn pending_timelocked_changes(&self) -> UnorderedSetMapper<usize>;
+
+    // epochs between queueing a change and when it can be executed.
+    // changing it goes through the timelock as well
+    #[storage_mapper("timelock_period")]
+    fn timelock_period(&self) -> SingleValueMapper<u64>;
+
+    // Queue
+    // - each setter queues its change, executeTimelockedChange applies it once the
+    //   effective epoch is reached and it can be cancelled until then
+
+    #[endpoint(setServiceFee)]
+    fn set_service_fee(&self, amount: BigUint) -> usize {
+        self.queue_timelocked_change(TimelockAction::SetServiceFee(amount))
+    }
+
+    #[endpoint(setDeltaStake)]
+    fn set_delta_stake(&self, amount: BigInt) -> usize {
+        self.queue_timelocked_change(TimelockAction::SetDeltaStake(amount))
+    }
+
+    #[endpoint(setTotalStaked)]
+    fn set_total_staked(&self, amount: BigUint) -> usize {
+        self.queue_timelocked_change(TimelockAction::SetTotalStaked(amount))
+    }
+
+    #[endpoint(setValidatorStakeAmount)]
+    fn set_validator_stake_amount(&self, validator: ManagedAddress, amount: BigUint) -> usize {
+        self.queue_timelocked_change(TimelockAction::SetValidatorStakeAmount(validator, amount))
+    }
+
+    #[endpoint(setTimelockPeriod)]
+    fn set_timelock_period(&self, epochs: u64) -> usize {
+        require!(epochs >= MIN_TIMELOCK_PERIOD, "Timelock period is too short");
+
+        self.queue_timelocked_change(TimelockAction::SetTimelockPeriod(epochs))
+    }
+
+    #[endpoint(clearValidatorStakeAmounts)]
+    fn clear_validator_stake_amounts(&self) -> usize {
+        self.queue_timelocked_change(TimelockAction::ClearValidatorStakeAmounts)
+    }
+
+    #[endpoint(setInstantUnstakeFee)]
+    fn set_instant_unstake_fee(&self, fee: u64) -> usize {
+        require!(fee <= crate::helpers::MAX_BPS, "Fee cannot exceed 100%");
+
+        self.queue_timelocked_change(TimelockAction::SetInstantUnstakeFee(fee))
+    }
+
+    // the validator set and the allocation are also changed by governance proposals,
+    // queueing the admin changes keeps them from undoing a passed proposal right away
+
+    #[endpoint(addValidator)]
+    fn add_validator(
+        &self,
+        address: ManagedAddress,
+        name: ManagedBuffer,
+        target_weight: u64,
+        cap: BigUint,
+    ) -> usize {
+        self.queue_timelocked_change(TimelockAction::AddValidator(address, name, target_weight, cap))
+    }
+
+    // the validator stops receiving stake and is the first one undelegated from
+    #[endpoint(retireValidator)]
+    fn retire_validator(&self, address: ManagedAddress) -> usize {
+        self.require_validator_registered(&address);
+
+        self.queue_timelocked_change(TimelockAction::RetireValidator(address))
+    }
+
+    #[endpoint(setValidatorWeight)]
+    fn set_validator_weight(&self, address: ManagedAddress, target_weight: u64) -> usize {
+        self.require_validator_registered(&address);
+
+        self.queue_timelocked_change(TimelockAction::SetValidatorWeight(address, target_weight))
+    }
+
+    #[endpoint(setValidatorCap)]
+    fn set_validator_cap(&self, address: ManagedAddress, cap: BigUint) -> usize {
+        self.require_validator_registered(&address);
+
+        self.queue_timelocked_change(TimelockAction::SetValidatorCap(address, cap))
+    }
+
+    #[endpoint(executeTimelockedChange)]
+    fn execute_timelocked_change(&self, id: usize) {
+        let mut change = self.require_pending_change(id);
+
+        require!(
+            self.blockchain().get_block_epoch() >= change.effective_epoch,
+            "Timelock is not over yet"
+        );
+
+        self.apply_timelock_action(&change.action);
+
+        change.status = TimelockStatus::Executed;
+        self.timelocked_changes().set(id, &change);
+        self.pending_timelocked_changes().swap_remove(&id);
+
+        self.timelock_executed_event(id, self.blockchain().get_block_epoch());
+    }
+
+    #[endpoint(cancelTimelockedChange)]
+    fn cancel_timelocked_change(&self, id: usize) {
+        let mut change = self.require_pending_change(id);
+
+        change.status = TimelockStatus::Cancelled;
+        self.timelocked_changes().set(id, &change);
+        self.pending_timelocked_changes().swap_remove(&id);
+
+        self.timelock_cancelled_event(id, self.blockchain().get_block_epoch());
+    }
+
+    // Views
+
+    #[view(getTimelockPeriod)]
+    fn get_timelock_period(&self) -> u64 {
+        if self.timelock_period().is_empty() {
+            DEFAULT_TIMELOCK_PERIOD
+        } else {
+            self.timelock_period().get()
+        }
+    }
+
+    #[view(getTimelockedChange)]
+    fn get_timelocked_change(&self, id: usize) -> TimelockedChange<Self::Api> {
+        require!(
+            id > 0 && id <= self.timelocked_changes().len(),
+            "Timelocked change does not exist"
+        );
+
+        self.timelocked_changes().get(id)
+    }
+
+    #[view(getPendingTimelockedChanges)]
+    fn get_pending_timelocked_changes(&self) -> MultiValueEncoded<TimelockedChange<Self::Api>> {
+        let mut result = MultiValueEncoded::new();
+        for id in self.pending_timelocked_changes().iter() {
+            result.push(self.timelocked_changes().get(id));
+        }
+
+        result
+    }
+
+    // Helpers
+
+    fn queue_timelocked_change(&self, action: TimelockAction<Self::Api>) -> usize {
+        self.require_role(self.get_timelock_role(&action));
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        let id = self.timelocked_changes().len() + 1;
+        let change = TimelockedChange {
+            id,
+            action,
+            status: TimelockStatus::Pending,
+            queued_epoch: current_epoch,
+            effective_epoch: current_epoch + self.get_timelock_period(),
+        };
+
+        self.timelocked_changes().push(&change);
+        self.pending_timelocked_changes().insert(id);
+
+        self.timelock_queued_event(id, change.effective_epoch, &change.action);
+
+        id
+    }
+
+    // same role as the setter that queued the change
+    fn require_pending_change(&self, id: usize) -> TimelockedChange<Self::Api> {
+        let change = self.get_timelocked_change(id);
+
+        require!(change.status == TimelockStatus::Pending, "Timelocked change is not pending");
+        self.require_role(self.get_timelock_role(&change.action));
+
+        change
+    }
+
+    fn get_timelock_role(&self, action: &TimelockAction<Self::Api>) -> Role {
+        match action {
+            TimelockAction::SetServiceFee(_) | TimelockAction::SetInstantUnstakeFee(_) => Role::FeeManager,
+            TimelockAction::AddValidator(..)
+            | TimelockAction::RetireValidator(_)
+            | TimelockAction::SetValidatorWeight(..)
+            | TimelockAction::SetValidatorCap(..) => Role::ValidatorManager,
+            _ => Role::Admin,
+        }
+    }
+
+    fn apply_timelock_action(&self, action: &TimelockAction<Self::Api>) {
+        match action {
+            TimelockAction::SetServiceFee(amount) => {
+                self.update_service_fee(amount.clone());
+            },
+            TimelockAction::SetDeltaStake(amount) => {
+                self.delta_stake().set(amount);
+            },
+            TimelockAction::SetTotalStaked(amount) => {
+                self.total_staked().set(amount);
+            },
+            TimelockAction::SetValidatorStakeAmount(validator, amount) => {
+                self.validator_stake_amount().insert(validator.clone(), amount.clone());
+            },
+            TimelockAction::SetTimelockPeriod(epochs) => {
+                self.timelock_period().set(*epochs);
+            },
+            TimelockAction::ClearValidatorStakeAmounts => {
+                self.validator_stake_amount().clear();
+            },
+            TimelockAction::SetInstantUnstakeFee(fee) => {
+                self.update_instant_unstake_fee(*fee);
+            },
+            TimelockAction::AddValidator(address, name, target_weight, cap) => {
+                self.register_validator(address.clone(), name.clone(), *target_weight, cap.clone());
+            },
+            TimelockAction::RetireValidator(address) => {
+                self.start_validator_retirement(address);
+            },
+            TimelockAction::SetValidatorWeight(address, target_weight) => {
+                self.update_validator_weight(address, *target_weight);
+            },
+            TimelockAction::SetValidatorCap(address, cap) => {
+                self.update_validator_cap(address, cap.clone());
+            },
+        }
+    }
+}
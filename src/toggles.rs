@@ -0,0 +1,111 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use elrond_wasm::storage::StorageKey;
+use elrond_wasm_modules::features::FEATURE_OFF;
+
+use crate::roles::Role;
+
+// user flows that can be switched off independently, all of them are on by default
+pub const STAKE_FEATURE: &[u8] = b"stake";
+pub const UNSTAKE_FEATURE: &[u8] = b"unstake";
+pub const INSTANT_UNSTAKE_FEATURE: &[u8] = b"instantUnstake";
+pub const CLAIM_FEATURE: &[u8] = b"claim";
+
+const USER_FEATURES: [&[u8]; 4] = [
+    STAKE_FEATURE,
+    UNSTAKE_FEATURE,
+    INSTANT_UNSTAKE_FEATURE,
+    CLAIM_FEATURE,
+];
+
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct PauseState {
+    pub paused: bool,
+    pub stake_enabled: bool,
+    pub unstake_enabled: bool,
+    pub instant_unstake_enabled: bool,
+    pub claim_enabled: bool,
+}
+
+// the switches are stored by the pause and features modules of elrond-wasm-modules.
+// the endpoints below add the Pauser role and the events, the owner-only pause, unpause
+// and setFeatureFlag of the modules stay available to the owner, without events
+#[elrond_wasm::module]
+pub trait TogglesModule:
+    crate::roles::RolesModule
+    + crate::events::EventsModule
+    + elrond_wasm_modules::pause::PauseModule
+    + elrond_wasm_modules::features::FeaturesModule
+    {
+
+    // stops every user flow at once, the feature switches are kept as they are
+    #[endpoint(pauseAll)]
+    fn pause_all(&self) {
+        self.require_role(Role::Pauser);
+        self.set_paused(true);
+
+        self.pause_state_changed_event(true);
+    }
+
+    #[endpoint(resumeAll)]
+    fn resume_all(&self) {
+        self.require_role(Role::Pauser);
+        self.set_paused(false);
+
+        self.pause_state_changed_event(false);
+    }
+
+    #[endpoint(pauseFeature)]
+    fn pause_feature(&self, feature: ManagedBuffer) {
+        self.set_user_feature(feature, false);
+    }
+
+    #[endpoint(resumeFeature)]
+    fn resume_feature(&self, feature: ManagedBuffer) {
+        self.set_user_feature(feature, true);
+    }
+
+    #[view(getPauseState)]
+    fn get_pause_state(&self) -> PauseState {
+        PauseState {
+            paused: self.is_paused(),
+            stake_enabled: self.is_feature_enabled(STAKE_FEATURE),
+            unstake_enabled: self.is_feature_enabled(UNSTAKE_FEATURE),
+            instant_unstake_enabled: self.is_feature_enabled(INSTANT_UNSTAKE_FEATURE),
+            claim_enabled: self.is_feature_enabled(CLAIM_FEATURE),
+        }
+    }
+
+    // Helpers
+
+    fn require_feature_enabled(&self, feature: &'static [u8]) {
+        self.require_not_paused();
+        self.check_feature_on(feature, true);
+    }
+
+    // FeatureName can't be built outside of its crate, the flag is read under the key
+    // feature_flag stores it at ("feat:" followed by the nested encoded name)
+    fn is_feature_enabled(&self, feature: &'static [u8]) -> bool {
+        let feature_name: ManagedBuffer = ManagedBuffer::new_from_bytes(feature);
+        let mut key: StorageKey<Self::Api> = StorageKey::new(&b"feat:"[..]);
+        key.append_item(&feature_name);
+
+        let flag: SingleValueMapper<u8> = SingleValueMapper::new(key);
+        flag.get() != FEATURE_OFF
+    }
+
+    fn set_user_feature(&self, feature: ManagedBuffer, enabled: bool) {
+        self.require_role(Role::Pauser);
+
+        let feature_bytes = feature.to_boxed_bytes();
+        require!(
+            USER_FEATURES.contains(&feature_bytes.as_slice()),
+            "Unknown feature"
+        );
+
+        self.set_feature_flag_endpoint(feature.clone(), enabled);
+
+        self.feature_toggled_event(&feature, enabled);
+    }
+}
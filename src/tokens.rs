@@ -1,21 +1,43 @@
 elrond_wasm::imports!();
 elrond_wasm::derive_imports!();
 
+use crate::roles::Role;
+
 const EGLD_NUM_DECIMALS: usize = 18;
 
-#[derive( 
-    TopEncode,
-    TopDecode,
-)]
+// uEGLD attributes: the EGLD owed to the holder and the epoch it unlocks at.
+// the uEGLD amount is minted 1:1 with the EGLD owed
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct TokenAttributes<M: ManagedTypeApi> {
+    pub unstake_epoch: u64,
+    pub unlock_epoch: u64,
+    pub egld_amount: BigUint<M>,
+}
+
+// why a position was or was not claimed
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, Copy, PartialEq)]
+pub enum ClaimStatus {
+    Claimed,
+    // the unbond period is not over yet
+    Locked,
+    // unlocked, but not enough EGLD was withdrawn from validators yet
+    NotWithdrawn,
+}
 
-pub struct TokenAttributes {
-    pub epoch: u64,
+// returned by claim for every uEGLD position sent, the ones not claimed were sent back
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct ClaimResult<M: ManagedTypeApi> {
+    pub nonce: u64,
+    pub egld_amount: BigUint<M>,
+    pub unlock_epoch: u64,
+    pub status: ClaimStatus,
 }
 
 #[elrond_wasm::module]
 pub trait TokenModule: 
     crate::events::EventsModule 
     + crate::storage::StorageModule
+    + crate::roles::RolesModule
  {
 
     /*
@@ -23,10 +45,11 @@ pub trait TokenModule:
     */ 
     // todo: replace this with .issue_and_set_all_roles
 
-      #[only_owner]
       #[payable("EGLD")]
       #[endpoint(issueToken)]
       fn issue_staked_egld(&self, token_display_name: ManagedBuffer, token_ticker: ManagedBuffer) {
+        self.require_role(Role::Admin);
+
         require!(
             self.staked_egld_id().is_empty(),
             "token was already issued"
@@ -62,9 +85,10 @@ pub trait TokenModule:
             .call_and_exit()
     }
 
-    #[only_owner]
     #[endpoint(setLocalRoles)]
     fn set_local_roles(&self) {
+        self.require_role(Role::Admin);
+
         require!(
             !self.staked_egld_id().is_empty(),
             "Must issue token first"
@@ -86,7 +110,6 @@ pub trait TokenModule:
         Create uEGLD (Undelegated EGLD meta-ESDT)
     */
 
-    #[only_owner]
     #[payable("EGLD")]
     #[endpoint(issueUndelegatedToken)]
     fn issue_undelegated_token(
@@ -95,6 +118,8 @@ pub trait TokenModule:
         token_ticker: ManagedBuffer,
         num_decimals: usize,
     ) {
+        self.require_role(Role::Admin);
+
         let caller = self.blockchain().get_caller();
         let payment_amount = self.call_value().egld_value();
 
@@ -114,7 +139,7 @@ pub trait TokenModule:
         &self,
         amount: BigUint,
         address: &ManagedAddress,
-        attributes: &TokenAttributes,
+        attributes: &TokenAttributes<Self::Api>,
     ) -> EsdtTokenPayment<Self::Api> {
 
         let created_tokens = self
@@ -0,0 +1,226 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::helpers::MAX_BPS;
+use crate::roles::Role;
+
+// weights are relative to each other, the bound keeps the allocation math within u64
+pub const MAX_VALIDATOR_WEIGHT: u64 = MAX_BPS;
+
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, Copy, PartialEq)]
+pub enum ValidatorStatus {
+    Active,
+    Paused,
+    Exiting,
+    Removed,
+}
+
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct ValidatorInfo<M: ManagedTypeApi> {
+    pub address: ManagedAddress<M>,
+    pub name: ManagedBuffer<M>,
+    pub status: ValidatorStatus,
+    pub target_weight: u64,
+    // maximum EGLD delegated to the validator (0 = no cap)
+    pub cap: BigUint<M>,
+    pub date_added: u64,
+}
+
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, Copy, PartialEq)]
+pub enum ExitPhase {
+    Unbonding,
+    Completed,
+}
+
+// progress of moving the stake off a retired validator
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct ValidatorExit<M: ManagedTypeApi> {
+    pub validator: ManagedAddress<M>,
+    pub phase: ExitPhase,
+    pub amount: BigUint<M>,
+    pub withdrawn_amount: BigUint<M>,
+    pub undelegate_epoch: u64,
+    pub unlock_epoch: u64,
+}
+
+#[elrond_wasm::module]
+pub trait ValidatorsModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    {
+
+    // Registry
+    // - active validators receive new stake according to their weight
+    // - paused validators keep their stake but receive nothing new
+    // - exiting validators are drained, then removed from the list
+    // - adding, retiring, weights and caps go through governance proposals or the timelock,
+    //   see TimelockModule. pausing stays immediate, it only stops new stake
+
+    #[endpoint(pauseValidator)]
+    fn pause_validator(&self, address: ManagedAddress) {
+        self.require_role(Role::ValidatorManager);
+
+        self.change_validator_status(&address, ValidatorStatus::Active, ValidatorStatus::Paused);
+    }
+
+    #[endpoint(resumeValidator)]
+    fn resume_validator(&self, address: ManagedAddress) {
+        self.require_role(Role::ValidatorManager);
+
+        self.change_validator_status(&address, ValidatorStatus::Paused, ValidatorStatus::Active);
+    }
+
+    // drops a retired validator from the list, once nothing is delegated to it anymore
+    #[endpoint(removeValidator)]
+    fn remove_validator(&self, address: ManagedAddress) {
+        self.require_role(Role::ValidatorManager);
+        self.require_no_maintenance_in_progress();
+
+        let mut info = self.require_validator_registered(&address);
+
+        require!(
+            info.status == ValidatorStatus::Exiting,
+            "Validator must be retired first"
+        );
+
+        let stake = match self.validator_stake_amount().get(&address) {
+            Some(n) => n,
+            None => BigUint::zero(),
+        };
+        require!(stake == 0, "Validator still has stake delegated");
+        require!(
+            self.validator_exit(&address).is_empty()
+                || self.validator_exit(&address).get().phase == ExitPhase::Completed,
+            "Validator exit still in progress"
+        );
+
+        for index in 1..=self.validators().len() {
+            if self.validators().get(index) == address {
+                self.validators().swap_remove(index);
+                break;
+            }
+        }
+
+        self.validator_stake_amount().remove(&address);
+        self.validator_stake_amount_clone().remove(&address);
+
+        info.status = ValidatorStatus::Removed;
+        self.validator_info(&address).set(&info);
+    }
+
+    // Views
+
+    #[view(getValidatorInfo)]
+    fn get_validator_info(&self, address: ManagedAddress) -> ValidatorInfo<Self::Api> {
+        self.require_validator_registered(&address)
+    }
+
+    #[view(getAllValidatorsInfo)]
+    fn get_all_validators_info(&self) -> MultiValueEncoded<ValidatorInfo<Self::Api>> {
+        let mut result = MultiValueEncoded::new();
+        for validator in self.validators().iter() {
+            if !self.validator_info(&validator).is_empty() {
+                result.push(self.validator_info(&validator).get());
+            }
+        }
+
+        result
+    }
+
+    // Setters
+    // - shared by governance proposals and the timelock
+
+    fn register_validator(
+        &self,
+        address: ManagedAddress,
+        name: ManagedBuffer,
+        target_weight: u64,
+        cap: BigUint,
+    ) {
+        require!(target_weight <= MAX_VALIDATOR_WEIGHT, "Validator weight is too high");
+
+        let info_mapper = self.validator_info(&address);
+
+        require!(
+            info_mapper.is_empty() || info_mapper.get().status == ValidatorStatus::Removed,
+            "Validator already registered"
+        );
+
+        if !self.is_in_validators_list(&address) {
+            self.validators().push(&address);
+        }
+
+        info_mapper.set(&ValidatorInfo {
+            address: address.clone(),
+            name,
+            status: ValidatorStatus::Active,
+            target_weight,
+            cap,
+            date_added: self.blockchain().get_block_timestamp(),
+        });
+    }
+
+    fn start_validator_retirement(&self, address: &ManagedAddress) {
+        let mut info = self.require_validator_registered(address);
+
+        require!(
+            info.status == ValidatorStatus::Active || info.status == ValidatorStatus::Paused,
+            "Validator is already retiring"
+        );
+
+        info.status = ValidatorStatus::Exiting;
+        self.validator_info(address).set(&info);
+    }
+
+    fn update_validator_weight(&self, address: &ManagedAddress, target_weight: u64) {
+        require!(target_weight <= MAX_VALIDATOR_WEIGHT, "Validator weight is too high");
+
+        let mut info = self.require_validator_registered(address);
+
+        info.target_weight = target_weight;
+        self.validator_info(address).set(&info);
+    }
+
+    fn update_validator_cap(&self, address: &ManagedAddress, cap: BigUint) {
+        let mut info = self.require_validator_registered(address);
+
+        info.cap = cap;
+        self.validator_info(address).set(&info);
+    }
+
+    // Helpers
+
+    fn require_validator_registered(&self, address: &ManagedAddress) -> ValidatorInfo<Self::Api> {
+        require!(
+            !self.validator_info(address).is_empty(),
+            "Validator not registered"
+        );
+
+        self.validator_info(address).get()
+    }
+
+    fn change_validator_status(
+        &self,
+        address: &ManagedAddress,
+        from: ValidatorStatus,
+        to: ValidatorStatus,
+    ) {
+        let mut info = self.require_validator_registered(address);
+
+        require!(info.status == from, "Invalid validator status for this operation");
+
+        info.status = to;
+        self.validator_info(address).set(&info);
+    }
+
+    fn is_in_validators_list(&self, address: &ManagedAddress) -> bool {
+        for validator in self.validators().iter() {
+            if &validator == address {
+                return true;
+            }
+        }
+
+        false
+    }
+}
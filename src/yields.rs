@@ -0,0 +1,164 @@
+elrond_wasm::imports!();
+elrond_wasm::derive_imports!();
+
+use crate::helpers::MAX_BPS;
+
+// an epoch lasts a day
+pub const EPOCHS_PER_YEAR: u64 = 365;
+
+// fixed point precision of the yield computations
+const YIELD_PRECISION: u64 = 1_000_000_000_000_000_000;
+
+// the windows returned by getYields
+const YIELD_WINDOWS: [u64; 3] = [7, 30, 90];
+
+// yearly yields in basis points, net yields are after the service fee
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone)]
+pub struct YieldInfo {
+    pub epochs: u64,
+    pub gross_apr: u64,
+    pub net_apr: u64,
+    pub gross_apy: u64,
+    pub net_apy: u64,
+}
+
+#[elrond_wasm::module]
+pub trait YieldModule:
+    crate::storage::StorageModule
+    + crate::roles::RolesModule
+    + crate::events::EventsModule
+    + crate::rate_history::RateHistoryModule
+    + crate::scoring::ScoringModule
+    {
+
+    // realised yield of stEGLD over the last `epochs` epochs, from the exchange rate history.
+    // the rate already excludes the fees, so it gives the net yield
+    #[view(getYield)]
+    fn get_yield(&self, epochs: u64) -> YieldInfo {
+        require!(epochs > 0, "Yield window must be at least 1 epoch");
+
+        let history_len = self.exchange_rate_history().len();
+        if history_len == 0 {
+            return self.build_yield_info(epochs, BigUint::zero(), false);
+        }
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        let window_start = if current_epoch > epochs { current_epoch - epochs } else { 0 };
+
+        // a shorter history is annualised over the epochs it covers
+        let start = self.exchange_rate_history().get(match self.find_rate_index(window_start) {
+            Some(index) => index,
+            None => 1,
+        });
+        let end = self.exchange_rate_history().get(history_len);
+
+        if end.epoch <= start.epoch || end.rate == 0 || end.rate >= start.rate {
+            return self.build_yield_info(epochs, BigUint::zero(), false);
+        }
+
+        // the EGLD value of stEGLD grows as the rate goes down
+        let precision = BigUint::from(YIELD_PRECISION);
+        let window_return = &start.rate * &precision / &end.rate - &precision;
+        let epoch_return = window_return / (end.epoch - start.epoch);
+
+        self.build_yield_info(epochs, epoch_return, false)
+    }
+
+    #[view(getYields)]
+    fn get_yields(&self) -> MultiValueEncoded<YieldInfo> {
+        let mut result = MultiValueEncoded::new();
+        for epochs in YIELD_WINDOWS.iter() {
+            result.push(self.get_yield(*epochs));
+        }
+
+        result
+    }
+
+    // realised yield of the stake delegated to a validator, from the rewards recorded for scoring.
+    // older rewards are pruned, so the window can't be longer than the scoring window
+    #[view(getValidatorYield)]
+    fn get_validator_yield(&self, validator: ManagedAddress, epochs: u64) -> YieldInfo {
+        require!(epochs > 0, "Yield window must be at least 1 epoch");
+        require!(
+            epochs <= self.get_scoring_window(),
+            "Yield window cannot exceed the scoring window"
+        );
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        let first_epoch = if current_epoch + 1 > epochs { current_epoch + 1 - epochs } else { 0 };
+
+        let mut total_stake = BigUint::zero();
+        let mut total_rewards = BigUint::zero();
+        for epoch in first_epoch..=current_epoch {
+            if let Some(performance) = self.validator_performance(&validator).get(&epoch) {
+                total_stake += performance.stake;
+                total_rewards += performance.rewards;
+            }
+        }
+
+        if total_stake == 0 {
+            return self.build_yield_info(epochs, BigUint::zero(), true);
+        }
+
+        // each epoch weighs by the stake it had, so this is the average yield per epoch
+        let epoch_return = total_rewards * BigUint::from(YIELD_PRECISION) / total_stake;
+
+        self.build_yield_info(epochs, epoch_return, true)
+    }
+
+    // Helpers
+
+    // epoch_return is the average return per epoch, scaled by YIELD_PRECISION
+    fn build_yield_info(&self, epochs: u64, epoch_return: BigUint, is_gross: bool) -> YieldInfo {
+        let service_fee = self.service_fee().get();
+        let fee_base = BigUint::from(1000u64);
+        let fee_kept = if service_fee < fee_base {
+            &fee_base - &service_fee
+        } else {
+            BigUint::zero()
+        };
+
+        let (gross_return, net_return) = if is_gross {
+            let net_return = &epoch_return * &fee_kept / &fee_base;
+            (epoch_return, net_return)
+        } else if fee_kept > 0 {
+            let gross_return = &epoch_return * &fee_base / &fee_kept;
+            (gross_return, epoch_return)
+        } else {
+            (epoch_return.clone(), epoch_return)
+        };
+
+        YieldInfo {
+            epochs,
+            gross_apr: self.to_bps(&(&gross_return * &BigUint::from(EPOCHS_PER_YEAR))),
+            net_apr: self.to_bps(&(&net_return * &BigUint::from(EPOCHS_PER_YEAR))),
+            gross_apy: self.to_bps(&self.compound_yearly(&gross_return)),
+            net_apy: self.to_bps(&self.compound_yearly(&net_return)),
+        }
+    }
+
+    // (1 + epoch_return) ^ EPOCHS_PER_YEAR - 1, by squaring
+    fn compound_yearly(&self, epoch_return: &BigUint) -> BigUint {
+        let precision = BigUint::from(YIELD_PRECISION);
+        let mut result = precision.clone();
+        let mut base = &precision + epoch_return;
+        let mut exponent = EPOCHS_PER_YEAR;
+
+        while exponent > 0 {
+            if exponent % 2 == 1 {
+                result = &result * &base / &precision;
+            }
+            base = &base * &base / &precision;
+            exponent /= 2;
+        }
+
+        result - precision
+    }
+
+    fn to_bps(&self, value: &BigUint) -> u64 {
+        match (value * &BigUint::from(MAX_BPS) / YIELD_PRECISION).to_u64() {
+            Some(bps) => bps,
+            None => u64::MAX,
+        }
+    }
+}
@@ -0,0 +1,49 @@
+#[test]
+fn unstake_claim_go() {
+    elrond_wasm_debug::mandos_go("mandos/unstake_claim.scen.json");
+}
+
+#[test]
+fn instant_unstake_go() {
+    elrond_wasm_debug::mandos_go("mandos/instant_unstake.scen.json");
+}
+
+#[test]
+fn exchange_rate_go() {
+    elrond_wasm_debug::mandos_go("mandos/exchange_rate.scen.json");
+}
+
+#[test]
+fn positions_go() {
+    elrond_wasm_debug::mandos_go("mandos/positions.scen.json");
+}
+
+#[test]
+fn batch_claim_go() {
+    elrond_wasm_debug::mandos_go("mandos/batch_claim.scen.json");
+}
+
+#[test]
+fn daily_delegation_go() {
+    elrond_wasm_debug::mandos_go("mandos/daily_delegation.scen.json");
+}
+
+#[test]
+fn maintenance_cycle_go() {
+    elrond_wasm_debug::mandos_go("mandos/maintenance_cycle.scen.json");
+}
+
+#[test]
+fn referral_go() {
+    elrond_wasm_debug::mandos_go("mandos/referral.scen.json");
+}
+
+#[test]
+fn governance_go() {
+    elrond_wasm_debug::mandos_go("mandos/governance.scen.json");
+}
+
+#[test]
+fn timelock_go() {
+    elrond_wasm_debug::mandos_go("mandos/timelock.scen.json");
+}
@@ -0,0 +1,66 @@
+use elrond_wasm_debug::*;
+
+fn world() -> BlockchainMock {
+    let mut blockchain = BlockchainMock::new();
+    blockchain.set_current_dir_from_workspace("");
+
+    blockchain.register_contract_builder(
+        "file:output/sc-liquid-staking-mock.wasm",
+        sc_liquid_staking_mock::ContractBuilder,
+    );
+    blockchain.register_contract_builder(
+        "file:delegation-mock/output/delegation-mock.wasm",
+        delegation_mock::ContractBuilder,
+    );
+    blockchain
+}
+
+#[test]
+fn unstake_claim_rs() {
+    elrond_wasm_debug::mandos_rs("mandos/unstake_claim.scen.json", world());
+}
+
+#[test]
+fn instant_unstake_rs() {
+    elrond_wasm_debug::mandos_rs("mandos/instant_unstake.scen.json", world());
+}
+
+#[test]
+fn exchange_rate_rs() {
+    elrond_wasm_debug::mandos_rs("mandos/exchange_rate.scen.json", world());
+}
+
+#[test]
+fn positions_rs() {
+    elrond_wasm_debug::mandos_rs("mandos/positions.scen.json", world());
+}
+
+#[test]
+fn batch_claim_rs() {
+    elrond_wasm_debug::mandos_rs("mandos/batch_claim.scen.json", world());
+}
+
+#[test]
+fn daily_delegation_rs() {
+    elrond_wasm_debug::mandos_rs("mandos/daily_delegation.scen.json", world());
+}
+
+#[test]
+fn maintenance_cycle_rs() {
+    elrond_wasm_debug::mandos_rs("mandos/maintenance_cycle.scen.json", world());
+}
+
+#[test]
+fn referral_rs() {
+    elrond_wasm_debug::mandos_rs("mandos/referral.scen.json", world());
+}
+
+#[test]
+fn governance_rs() {
+    elrond_wasm_debug::mandos_rs("mandos/governance.scen.json", world());
+}
+
+#[test]
+fn timelock_rs() {
+    elrond_wasm_debug::mandos_rs("mandos/timelock.scen.json", world());
+}